@@ -0,0 +1,983 @@
+//! Agent adapters: where and how each AI agent expects its rule files.
+//!
+//! The install pipeline always produces the same markdown files — the core workflow under
+//! `<rules_folder>/rules/` and the rule details under `<details_parent>/aws-aidlc-rule-details/`.
+//! An [`AgentAdapter`] decides where those folders are for its agent, how the rule files
+//! are named, what front matter they need, how references between files are rewritten and
+//! what to tell the user afterwards.
+//!
+//! Supporting a new agent means implementing the trait and listing it in [`built_in`];
+//! the prompts, the install pipeline and the maintenance subcommands pick it up from there.
+
+use crate::config::{Config, KiroConfig};
+use crate::{markdown, patch};
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the core workflow file as extracted from the release.
+pub const CORE_WORKFLOW_FILE: &str = "core-workflow.md";
+
+/// Name of the helper section written into an agent's entry file.
+const ENTRY_BLOCK_NAME: &str = "aidlc-workflow";
+
+/// How rule files are installed for one AI agent.
+pub trait AgentAdapter {
+    /// Stable identifier, recorded in the integrity manifest.
+    fn id(&self) -> &str;
+
+    /// One-line description shown in the target selection menu.
+    fn description(&self) -> &str;
+
+    /// What the target selection menu lists the agent as.
+    fn label(&self) -> String {
+        self.rules_folder().to_string()
+    }
+
+    /// Folder the `rules/` directory is installed into, relative to the project root.
+    fn rules_folder(&self) -> &str;
+
+    /// Folder `aws-aidlc-rule-details/` is installed into, relative to the project root.
+    fn details_parent(&self) -> &str;
+
+    /// Final location of a rule file the pipeline wrote at `file` (relative to the
+    /// project root). Only called for files under `<rules_folder>/rules/`. Agents that
+    /// don't read subfolders can move the files up into the rules folder itself.
+    fn installed_name(&self, file: &Path) -> PathBuf {
+        file.to_path_buf()
+    }
+
+    /// Front matter to put at the top of the rule file the pipeline wrote at `file`, if
+    /// the agent needs any. Only called for files under `<rules_folder>/rules/`.
+    fn front_matter(&self, _file: &Path) -> Option<String> {
+        None
+    }
+
+    /// Point references to the rule details in `files` (relative to `root`) at where they
+    /// were installed. Returns the files that changed.
+    fn rewrite_paths(
+        &self,
+        root: &Path,
+        files: &[PathBuf],
+        details_parent: &str,
+    ) -> Result<Vec<PathBuf>> {
+        patch::patch_rule_details_path(root, files, details_parent)
+    }
+
+    /// Tips printed once the installation is complete.
+    fn post_install_hints(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The settings the adapter was set up with, if it has any. They shape the installed
+    /// files, so the integrity manifest records them.
+    fn settings(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Set the adapter up again with `settings` recorded from [`settings`](Self::settings).
+    fn load_settings(&mut self, _settings: &serde_json::Value) -> Result<()> {
+        Ok(())
+    }
+
+    /// Documented maximum size of one rule file, in characters. Longer files are cut off
+    /// by the agent.
+    fn max_rule_chars(&self) -> Option<usize> {
+        None
+    }
+
+    /// A file the user owns and the agent always reads, relative to the project root. The
+    /// helper keeps a marker-delimited block in it pointing at the installed rules and
+    /// leaves the rest of the file alone.
+    fn entry_file(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Body of the block written into [`entry_file`](Self::entry_file).
+    fn entry_block(&self, details_parent: &str) -> String {
+        format!(
+            "## AI-DLC Workflow\n\
+             This project follows the AI-DLC workflow. **MANDATORY**: read and follow \
+             `{}`, and load the rule details it references from \
+             `{details_parent}/aws-aidlc-rule-details/` when it asks for them.\n",
+            self.core_workflow_path()
+                .to_string_lossy()
+                .replace('\\', "/")
+        )
+    }
+
+    /// Final location of the core workflow file, relative to the project root.
+    fn core_workflow_path(&self) -> PathBuf {
+        self.installed_name(
+            &Path::new(self.rules_folder())
+                .join("rules")
+                .join(CORE_WORKFLOW_FILE),
+        )
+    }
+}
+
+/// When Kiro loads a steering file, set by its `inclusion` front matter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Inclusion {
+    /// In every interaction.
+    #[default]
+    Always,
+    /// When a file matching `fileMatchPattern` is in context.
+    FileMatch,
+    /// Only when referenced in chat with `#<file name>`.
+    Manual,
+}
+
+/// Kiro IDE / Kiro CLI steering files, with front matter setting when the core workflow
+/// is loaded.
+#[derive(Debug, Default)]
+pub struct Kiro {
+    pub inclusion: Inclusion,
+    /// Required for [`Inclusion::FileMatch`].
+    pub file_match_pattern: Option<String>,
+}
+
+impl Kiro {
+    /// A Kiro adapter using the project's `[kiro]` settings.
+    pub fn new(config: &KiroConfig) -> Self {
+        Self {
+            inclusion: config.inclusion,
+            file_match_pattern: config.file_match_pattern.clone(),
+        }
+    }
+}
+
+impl AgentAdapter for Kiro {
+    fn id(&self) -> &str {
+        "kiro"
+    }
+
+    fn description(&self) -> &str {
+        "Kiro IDE / Kiro CLI steering files"
+    }
+
+    fn rules_folder(&self) -> &str {
+        ".kiro/steering"
+    }
+
+    fn details_parent(&self) -> &str {
+        ".kiro"
+    }
+
+    fn front_matter(&self, _file: &Path) -> Option<String> {
+        Some(match (self.inclusion, &self.file_match_pattern) {
+            (Inclusion::FileMatch, Some(pattern)) => format!(
+                "inclusion: fileMatch\nfileMatchPattern: \"{}\"\n",
+                pattern.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            (Inclusion::Manual, _) => "inclusion: manual\n".to_string(),
+            _ => "inclusion: always\n".to_string(),
+        })
+    }
+
+    fn post_install_hints(&self) -> Vec<String> {
+        vec![match self.inclusion {
+            Inclusion::Always => {
+                "Kiro picks up the steering files automatically — no restart needed.".to_string()
+            }
+            Inclusion::FileMatch => format!(
+                "Kiro loads the workflow when a file matching {} is in context.",
+                self.file_match_pattern.as_deref().unwrap_or_default()
+            ),
+            Inclusion::Manual => {
+                "Type #core-workflow in Kiro chat to load the workflow.".to_string()
+            }
+        }]
+    }
+
+    fn settings(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(KiroConfig {
+            inclusion: self.inclusion,
+            file_match_pattern: self.file_match_pattern.clone(),
+        })
+        .ok()
+    }
+
+    fn load_settings(&mut self, settings: &serde_json::Value) -> Result<()> {
+        *self = Self::new(&KiroConfig::deserialize(settings)?);
+        Ok(())
+    }
+}
+
+/// Amazon Q Developer IDE plugin rules.
+pub struct AmazonQ;
+
+impl AgentAdapter for AmazonQ {
+    fn id(&self) -> &str {
+        "amazonq"
+    }
+
+    fn description(&self) -> &str {
+        "Amazon Q Developer IDE plugin"
+    }
+
+    fn rules_folder(&self) -> &str {
+        ".amazonq/rules"
+    }
+
+    fn details_parent(&self) -> &str {
+        ".amazonq"
+    }
+
+    fn post_install_hints(&self) -> Vec<String> {
+        vec![
+            "In the Amazon Q chat panel, open Rules to check that core-workflow.md is enabled."
+                .to_string(),
+        ]
+    }
+}
+
+/// Cursor project rules: `.mdc` files with front matter. The core workflow is always
+/// applied; the rule details live outside `.cursor/rules/`, so Cursor only reads them
+/// when the workflow points the agent at them.
+pub struct Cursor;
+
+impl AgentAdapter for Cursor {
+    fn id(&self) -> &str {
+        "cursor"
+    }
+
+    fn description(&self) -> &str {
+        "Cursor AI editor"
+    }
+
+    fn rules_folder(&self) -> &str {
+        ".cursor/rules"
+    }
+
+    fn details_parent(&self) -> &str {
+        ".cursor"
+    }
+
+    fn installed_name(&self, file: &Path) -> PathBuf {
+        file.with_extension("mdc")
+    }
+
+    fn front_matter(&self, _file: &Path) -> Option<String> {
+        Some(
+            "description: AI-DLC workflow — load the rule details it references on demand\n\
+             globs:\n\
+             alwaysApply: true\n"
+                .to_string(),
+        )
+    }
+
+    fn post_install_hints(&self) -> Vec<String> {
+        vec![
+            "core-workflow.mdc is always applied; check Cursor Settings → Rules to see it."
+                .to_string(),
+            format!(
+                "Rule details in {}/aws-aidlc-rule-details/ are loaded only when the workflow asks for them.",
+                self.details_parent()
+            ),
+        ]
+    }
+}
+
+/// GitHub Copilot: the core workflow as a path-specific instructions file applying to
+/// every file, plus a block in the repository-wide `copilot-instructions.md`.
+pub struct Copilot;
+
+impl AgentAdapter for Copilot {
+    fn id(&self) -> &str {
+        "copilot"
+    }
+
+    fn description(&self) -> &str {
+        "GitHub Copilot (VS Code, JetBrains, github.com)"
+    }
+
+    fn rules_folder(&self) -> &str {
+        ".github/instructions"
+    }
+
+    fn details_parent(&self) -> &str {
+        ".github"
+    }
+
+    fn installed_name(&self, file: &Path) -> PathBuf {
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+        file.with_file_name(format!("{stem}.instructions.md"))
+    }
+
+    fn front_matter(&self, _file: &Path) -> Option<String> {
+        Some("applyTo: \"**\"\n".to_string())
+    }
+
+    fn entry_file(&self) -> Option<PathBuf> {
+        Some(PathBuf::from(".github/copilot-instructions.md"))
+    }
+
+    fn post_install_hints(&self) -> Vec<String> {
+        vec![
+            "In VS Code, make sure the github.copilot.chat.codeGeneration.useInstructionFiles setting is on."
+                .to_string(),
+        ]
+    }
+}
+
+/// Agents that read one instruction file at the project root — `AGENTS.md`, `CLAUDE.md`,
+/// `GEMINI.md`. The rules go in a neutral `.aidlc/` folder and a block in the root file,
+/// which teams usually maintain by hand, points the agent at them.
+pub struct RootFile {
+    id: &'static str,
+    file: &'static str,
+    description: &'static str,
+}
+
+impl RootFile {
+    /// `AGENTS.md`, read by Codex, Jules, Aider and most other agents.
+    pub const AGENTS_MD: Self = Self {
+        id: "agents-md",
+        file: "AGENTS.md",
+        description: "Codex, Jules, Aider and other AGENTS.md readers",
+    };
+    /// `CLAUDE.md`, read by Claude-based agents.
+    pub const CLAUDE_MD: Self = Self {
+        id: "claude-md",
+        file: "CLAUDE.md",
+        description: "Claude-based coding agents",
+    };
+    /// `GEMINI.md`, read by Gemini CLI.
+    pub const GEMINI_MD: Self = Self {
+        id: "gemini-md",
+        file: "GEMINI.md",
+        description: "Gemini CLI",
+    };
+}
+
+impl AgentAdapter for RootFile {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+
+    fn label(&self) -> String {
+        self.file.to_string()
+    }
+
+    fn rules_folder(&self) -> &str {
+        ".aidlc"
+    }
+
+    fn details_parent(&self) -> &str {
+        ".aidlc"
+    }
+
+    fn entry_file(&self) -> Option<PathBuf> {
+        Some(PathBuf::from(self.file))
+    }
+
+    fn post_install_hints(&self) -> Vec<String> {
+        vec![format!(
+            "Only the AI-DLC block in {} is managed — edit the rest of the file freely. `aidlc-workflows-helper uninstall` removes the block again.",
+            self.file
+        )]
+    }
+}
+
+/// Windsurf workspace rules. Windsurf reads `.windsurf/rules/` without subfolders and
+/// truncates each rule at 12,000 characters; the rule details stay outside it.
+pub struct Windsurf;
+
+impl AgentAdapter for Windsurf {
+    fn id(&self) -> &str {
+        "windsurf"
+    }
+
+    fn description(&self) -> &str {
+        "Windsurf (Cascade) workspace rules"
+    }
+
+    fn rules_folder(&self) -> &str {
+        ".windsurf/rules"
+    }
+
+    fn details_parent(&self) -> &str {
+        ".windsurf"
+    }
+
+    fn installed_name(&self, file: &Path) -> PathBuf {
+        move_up(self.rules_folder(), file)
+    }
+
+    fn front_matter(&self, _file: &Path) -> Option<String> {
+        Some("trigger: always_on\n".to_string())
+    }
+
+    fn max_rule_chars(&self) -> Option<usize> {
+        Some(12_000)
+    }
+}
+
+/// Cline reads every file in `.clinerules/` as a rule, so only the core workflow goes
+/// there; the rule details go in the neutral `.aidlc/` folder.
+pub struct Cline;
+
+impl AgentAdapter for Cline {
+    fn id(&self) -> &str {
+        "cline"
+    }
+
+    fn description(&self) -> &str {
+        "Cline (VS Code extension)"
+    }
+
+    fn rules_folder(&self) -> &str {
+        ".clinerules"
+    }
+
+    fn details_parent(&self) -> &str {
+        ".aidlc"
+    }
+
+    fn installed_name(&self, file: &Path) -> PathBuf {
+        move_up(self.rules_folder(), file)
+    }
+}
+
+/// Roo Code workspace rules, read from `.roo/rules/` including subfolders.
+pub struct Roo;
+
+impl AgentAdapter for Roo {
+    fn id(&self) -> &str {
+        "roo"
+    }
+
+    fn description(&self) -> &str {
+        "Roo Code (VS Code extension)"
+    }
+
+    fn rules_folder(&self) -> &str {
+        ".roo/rules"
+    }
+
+    fn details_parent(&self) -> &str {
+        ".roo"
+    }
+}
+
+/// A rules folder chosen by the user, with no agent-specific handling.
+pub struct Custom {
+    rules_folder: String,
+    details_parent: String,
+}
+
+impl Custom {
+    /// The rule details go one level up from `rules_folder`, e.g. `.ai/rules` → `.ai`.
+    /// A single-level folder is its own details parent. Fails unless `rules_folder` is a
+    /// plain relative path, so nothing is installed outside the project.
+    pub fn new(rules_folder: &str) -> Result<Self> {
+        let rules_folder = rules_folder.trim().trim_end_matches('/');
+        if rules_folder.is_empty()
+            || !Path::new(rules_folder)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            bail!(
+                "The rules folder `{rules_folder}` must be a relative path inside the project, without `.` or `..`"
+            );
+        }
+        Ok(Self {
+            rules_folder: rules_folder.to_string(),
+            details_parent: rules_folder
+                .rsplit_once('/')
+                .map_or(rules_folder, |(parent, _)| parent)
+                .to_string(),
+        })
+    }
+}
+
+impl AgentAdapter for Custom {
+    fn id(&self) -> &str {
+        "custom"
+    }
+
+    fn description(&self) -> &str {
+        "Custom path"
+    }
+
+    fn rules_folder(&self) -> &str {
+        &self.rules_folder
+    }
+
+    fn details_parent(&self) -> &str {
+        &self.details_parent
+    }
+}
+
+/// Every built-in agent, in menu order, set up from the project `config`.
+pub fn built_in(config: &Config) -> Vec<Box<dyn AgentAdapter>> {
+    vec![
+        Box::new(Kiro::new(&config.kiro)),
+        Box::new(AmazonQ),
+        Box::new(Cursor),
+        Box::new(Copilot),
+        Box::new(Windsurf),
+        Box::new(Cline),
+        Box::new(Roo),
+        Box::new(RootFile::AGENTS_MD),
+        Box::new(RootFile::CLAUDE_MD),
+        Box::new(RootFile::GEMINI_MD),
+    ]
+}
+
+/// Write or update the agent's block in its entry file under `root`. Does nothing for
+/// agents without an entry file.
+pub fn write_entry_block(
+    agent: &dyn AgentAdapter,
+    root: &Path,
+    details_parent: &str,
+) -> Result<()> {
+    let Some(file) = agent.entry_file() else {
+        return Ok(());
+    };
+    let body = agent.entry_block(details_parent);
+    patch::patch_file_section(&root.join(file), ENTRY_BLOCK_NAME, Some(&body))
+}
+
+/// Remove the agent's block from its entry file under `root`, deleting the file if
+/// nothing else is left in it.
+pub fn remove_entry_block(agent: &dyn AgentAdapter, root: &Path) -> Result<()> {
+    let Some(file) = agent.entry_file() else {
+        return Ok(());
+    };
+    patch::patch_file_section(&root.join(file), ENTRY_BLOCK_NAME, None)
+}
+
+/// Where agents installed together keep their one shared details tree: the agents'
+/// common details parent if they agree on one, else `.aidlc`.
+pub fn shared_details_parent(agents: &[&dyn AgentAdapter]) -> String {
+    match agents.split_first() {
+        Some((first, rest))
+            if rest
+                .iter()
+                .all(|a| a.details_parent() == first.details_parent()) =>
+        {
+            first.details_parent().to_string()
+        }
+        _ => ".aidlc".to_string(),
+    }
+}
+
+/// The adapter named by an `--agent` value: a built-in id, or `custom:<rules folder>`.
+fn from_arg(arg: &str, config: &Config) -> Result<Box<dyn AgentAdapter>> {
+    if let Some(rules_folder) = arg.strip_prefix("custom:") {
+        if rules_folder.trim().is_empty() {
+            bail!("`--agent custom:` needs a rules folder, e.g. `custom:.ai/rules`");
+        }
+        return Ok(Box::new(Custom::new(rules_folder)?));
+    }
+    let mut agents = built_in(config);
+    match agents.iter().position(|a| a.id() == arg) {
+        Some(index) => Ok(agents.swap_remove(index)),
+        None => {
+            let ids: Vec<&str> = agents.iter().map(|a| a.id()).collect();
+            bail!(
+                "Unknown agent `{arg}`; expected one of {} or `custom:<rules folder>`",
+                ids.join(", ")
+            )
+        }
+    }
+}
+
+/// The adapters named by the `--agent` values, each once: repeating an agent (same id
+/// and rules folder) doesn't install it twice.
+pub fn from_args(args: &[String], config: &Config) -> Result<Vec<Box<dyn AgentAdapter>>> {
+    let mut agents: Vec<Box<dyn AgentAdapter>> = Vec::new();
+    for arg in args {
+        let agent = from_arg(arg, config)?;
+        if !agents
+            .iter()
+            .any(|a| a.id() == agent.id() && a.rules_folder() == agent.rules_folder())
+        {
+            agents.push(agent);
+        }
+    }
+    Ok(agents)
+}
+
+/// The adapter recorded in a manifest: the built-in one with id `id`, else the built-in
+/// one installing into `rules_folder` (manifests from before adapters were recorded),
+/// else a [`Custom`] one.
+pub fn resolve(
+    id: Option<&str>,
+    rules_folder: &str,
+    config: &Config,
+) -> Result<Box<dyn AgentAdapter>> {
+    let mut agents = built_in(config);
+    let index = agents
+        .iter()
+        .position(|a| Some(a.id()) == id)
+        .or_else(|| agents.iter().position(|a| a.rules_folder() == rules_folder));
+    Ok(match index {
+        Some(index) => agents.swap_remove(index),
+        None => Box::new(Custom::new(rules_folder)?),
+    })
+}
+
+/// Apply the agent's front matter and file names to the rule files among `files`
+/// (relative to `root`), updating `files` to the final names. References to a renamed
+/// file by name are updated to the new name in the agent's own markdown files and, unless
+/// `shared_details` says other agents read them too, in the rule details.
+pub fn finish(
+    agent: &dyn AgentAdapter,
+    root: &Path,
+    files: &mut [PathBuf],
+    shared_details: bool,
+) -> Result<()> {
+    let rules_dir = Path::new(agent.rules_folder()).join("rules");
+    let mut renamed = Vec::new();
+    for file in files.iter_mut() {
+        if !file.starts_with(&rules_dir) {
+            continue;
+        }
+        if let Some(front_matter) = agent.front_matter(file) {
+            let path = root.join(&*file);
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            fs::write(&path, format!("---\n{front_matter}---\n\n{content}"))?;
+        }
+        let name = agent.installed_name(file);
+        if name != *file {
+            fs::rename(root.join(&*file), root.join(&name))
+                .with_context(|| format!("Failed to rename {}", file.display()))?;
+            if file_name(file) != file_name(&name) {
+                renamed.push((file_name(file), file_name(&name)));
+            }
+            *file = name;
+        }
+    }
+    // Left empty when the agent moved every rule file up; non-empty folders stay.
+    fs::remove_dir(root.join(&rules_dir)).ok();
+    if !renamed.is_empty() {
+        let scope: Vec<PathBuf> = files
+            .iter()
+            .filter(|f| !shared_details || f.starts_with(agent.rules_folder()))
+            .cloned()
+            .collect();
+        rename_references(root, &scope, &renamed)?;
+    }
+    Ok(())
+}
+
+/// Replace mentions of each old file name in `renamed` with the new one, outside code
+/// blocks, in every markdown file among `files`.
+fn rename_references(root: &Path, files: &[PathBuf], renamed: &[(String, String)]) -> Result<()> {
+    let patterns: Vec<(Regex, &str)> = renamed
+        .iter()
+        .map(|(old, new)| {
+            let pattern = format!(r"\b{}\b", regex::escape(old));
+            (
+                Regex::new(&pattern).expect("escaped file name"),
+                new.as_str(),
+            )
+        })
+        .collect();
+    for file in files.iter().filter(|f| is_markdown(f)) {
+        let path = root.join(file);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let updated = markdown::map_prose(&content, |text| {
+            patterns
+                .iter()
+                .fold(text.to_string(), |text, (regex, new)| {
+                    regex.replace_all(&text, *new).into_owned()
+                })
+        });
+        if updated != content {
+            fs::write(&path, updated)?;
+        }
+    }
+    Ok(())
+}
+
+/// Rule files among `files` (relative to `root`) that are longer than the agent reads,
+/// with their length in characters.
+pub fn oversized_rules(
+    agent: &dyn AgentAdapter,
+    root: &Path,
+    files: &[PathBuf],
+) -> Result<Vec<(PathBuf, usize)>> {
+    let Some(limit) = agent.max_rule_chars() else {
+        return Ok(Vec::new());
+    };
+    let mut oversized = Vec::new();
+    for file in files.iter().filter(|f| f.starts_with(agent.rules_folder())) {
+        let content = fs::read_to_string(root.join(file))
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let chars = content.chars().count();
+        if chars > limit {
+            oversized.push((file.clone(), chars));
+        }
+    }
+    Ok(oversized)
+}
+
+/// `file` moved from `<rules_folder>/rules/` up into `rules_folder`.
+fn move_up(rules_folder: &str, file: &Path) -> PathBuf {
+    Path::new(rules_folder).join(file.file_name().unwrap_or_default())
+}
+
+/// True for the markdown flavors agents read: `.md` and Cursor's `.mdc`.
+pub fn is_markdown(file: &Path) -> bool {
+    file.extension()
+        .is_some_and(|ext| ext == "md" || ext == "mdc")
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renames rule files to `.mdc` and tags them, to exercise [`finish`].
+    struct Tagged;
+
+    impl AgentAdapter for Tagged {
+        fn id(&self) -> &str {
+            "tagged"
+        }
+
+        fn description(&self) -> &str {
+            "Test agent"
+        }
+
+        fn rules_folder(&self) -> &str {
+            ".tagged"
+        }
+
+        fn details_parent(&self) -> &str {
+            ".tagged"
+        }
+
+        fn installed_name(&self, file: &Path) -> PathBuf {
+            file.with_extension("mdc")
+        }
+
+        fn front_matter(&self, _file: &Path) -> Option<String> {
+            Some("tag: true\n".to_string())
+        }
+    }
+
+    #[test]
+    fn custom_details_parent_is_one_level_up() {
+        assert_eq!(Custom::new(".ai/rules").unwrap().details_parent(), ".ai");
+        assert_eq!(Custom::new("a/b/rules/").unwrap().details_parent(), "a/b");
+        assert_eq!(Custom::new("rules").unwrap().details_parent(), "rules");
+    }
+
+    #[test]
+    fn custom_rules_folder_stays_inside_the_project() {
+        for folder in [
+            "",
+            "/",
+            "/etc/rules",
+            "../rules",
+            ".ai/../../rules",
+            "./rules",
+            ".",
+        ] {
+            assert!(Custom::new(folder).is_err(), "{folder:?} was accepted");
+        }
+        assert!(from_arg("custom:../outside", &Config::default()).is_err());
+    }
+
+    #[test]
+    fn agents_share_their_details_parent_or_aidlc() {
+        let kiro = Kiro::default();
+        assert_eq!(shared_details_parent(&[&kiro]), ".kiro");
+        assert_eq!(
+            shared_details_parent(&[&Cline, &RootFile::CLAUDE_MD]),
+            ".aidlc"
+        );
+        assert_eq!(shared_details_parent(&[&kiro, &Cursor]), ".aidlc");
+    }
+
+    #[test]
+    fn parses_agent_args() {
+        let config = Config::default();
+        assert_eq!(from_arg("cursor", &config).unwrap().id(), "cursor");
+        let custom = from_arg("custom:.ai/rules", &config).unwrap();
+        assert_eq!(custom.rules_folder(), ".ai/rules");
+        assert!(from_arg("custom:", &config).is_err());
+        let Err(error) = from_arg("vim", &config) else {
+            panic!("unknown agent accepted");
+        };
+        let error = error.to_string();
+        assert!(error.contains("kiro, amazonq"), "{error}");
+    }
+
+    #[test]
+    fn repeated_agent_args_are_installed_once() {
+        let args = [
+            "kiro",
+            "custom:.ai/rules",
+            "kiro",
+            "custom:.ai/rules",
+            "claude-md",
+        ]
+        .map(String::from);
+        let agents = from_args(&args, &Config::default()).unwrap();
+        let ids: Vec<&str> = agents.iter().map(|a| a.id()).collect();
+        assert_eq!(ids, ["kiro", "custom", "claude-md"]);
+    }
+
+    #[test]
+    fn resolves_by_id_then_rules_folder() {
+        assert_eq!(
+            resolve(Some("cursor"), ".elsewhere", &Config::default())
+                .unwrap()
+                .id(),
+            "cursor"
+        );
+        assert_eq!(
+            resolve(None, ".kiro/steering", &Config::default())
+                .unwrap()
+                .id(),
+            "kiro"
+        );
+        let custom = resolve(Some("gone"), ".ai/rules", &Config::default()).unwrap();
+        assert_eq!(custom.id(), "custom");
+        assert_eq!(custom.details_parent(), ".ai");
+    }
+
+    #[test]
+    fn finish_adds_front_matter_and_renames_rule_files_only() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".tagged/rules")).unwrap();
+        fs::create_dir_all(dir.path().join(".tagged/aws-aidlc-rule-details")).unwrap();
+        fs::write(
+            dir.path().join(".tagged/rules/core-workflow.md"),
+            "# Core\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(".tagged/aws-aidlc-rule-details/a.md"),
+            "Back to `core-workflow.md`.\n\n```\ncat core-workflow.md\n```\n",
+        )
+        .unwrap();
+        let mut files = vec![
+            PathBuf::from(".tagged/aws-aidlc-rule-details/a.md"),
+            PathBuf::from(".tagged/rules/core-workflow.md"),
+        ];
+
+        finish(&Tagged, dir.path(), &mut files, false).unwrap();
+
+        assert_eq!(
+            files[0],
+            PathBuf::from(".tagged/aws-aidlc-rule-details/a.md")
+        );
+        assert_eq!(files[1], Tagged.core_workflow_path());
+        assert_eq!(
+            fs::read_to_string(dir.path().join(&files[1])).unwrap(),
+            "---\ntag: true\n---\n\n# Core\n"
+        );
+        assert!(!dir.path().join(".tagged/rules/core-workflow.md").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join(&files[0])).unwrap(),
+            "Back to `core-workflow.mdc`.\n\n```\ncat core-workflow.md\n```\n"
+        );
+    }
+
+    #[test]
+    fn cursor_installs_an_always_applied_mdc_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".cursor/rules/rules")).unwrap();
+        fs::write(
+            dir.path().join(".cursor/rules/rules/core-workflow.md"),
+            "# Core\n",
+        )
+        .unwrap();
+        let mut files = vec![PathBuf::from(".cursor/rules/rules/core-workflow.md")];
+
+        finish(&Cursor, dir.path(), &mut files, false).unwrap();
+
+        assert_eq!(
+            files,
+            [PathBuf::from(".cursor/rules/rules/core-workflow.mdc")]
+        );
+        let content = fs::read_to_string(dir.path().join(&files[0])).unwrap();
+        assert!(content.starts_with("---\ndescription: "));
+        assert!(content.contains("\nalwaysApply: true\n---\n\n# Core\n"));
+    }
+
+    #[test]
+    fn entry_block_is_added_and_removed_without_touching_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let agents_md = dir.path().join("AGENTS.md");
+        fs::write(&agents_md, "# Team\n\nRun `make test`.\n").unwrap();
+        let agent = RootFile::AGENTS_MD;
+
+        write_entry_block(&agent, dir.path(), ".aidlc").unwrap();
+        write_entry_block(&agent, dir.path(), ".aidlc").unwrap();
+        let content = fs::read_to_string(&agents_md).unwrap();
+        assert!(content.starts_with("# Team\n\nRun `make test`.\n\n<!-- aidlc-helper:begin"));
+        assert!(content.contains("`.aidlc/rules/core-workflow.md`"));
+        assert_eq!(content.matches("aidlc-helper:begin").count(), 1);
+
+        remove_entry_block(&agent, dir.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&agents_md).unwrap(),
+            "# Team\n\nRun `make test`.\n"
+        );
+
+        let claude_md = dir.path().join("CLAUDE.md");
+        write_entry_block(&RootFile::CLAUDE_MD, dir.path(), ".aidlc").unwrap();
+        assert!(claude_md.exists());
+        remove_entry_block(&RootFile::CLAUDE_MD, dir.path()).unwrap();
+        assert!(!claude_md.exists());
+    }
+
+    #[test]
+    fn reports_rule_files_over_the_agent_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".windsurf/rules")).unwrap();
+        fs::create_dir_all(dir.path().join(".windsurf/aws-aidlc-rule-details")).unwrap();
+        fs::write(
+            dir.path().join(".windsurf/rules/core-workflow.md"),
+            "é".repeat(12_001),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(".windsurf/rules/short.md"),
+            "é".repeat(12_000),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(".windsurf/aws-aidlc-rule-details/long.md"),
+            "x".repeat(20_000),
+        )
+        .unwrap();
+        let files = [
+            PathBuf::from(".windsurf/rules/core-workflow.md"),
+            PathBuf::from(".windsurf/rules/short.md"),
+            PathBuf::from(".windsurf/aws-aidlc-rule-details/long.md"),
+        ];
+
+        assert_eq!(
+            oversized_rules(&Windsurf, dir.path(), &files).unwrap(),
+            [(files[0].clone(), 12_001)]
+        );
+        assert!(
+            oversized_rules(&Roo, dir.path(), &files)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
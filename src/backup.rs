@@ -0,0 +1,281 @@
+//! Automatic backups of installed rules, taken before every overwrite.
+//!
+//! Each backup is a copy of the install targets (rules folder, rule details and integrity
+//! manifest) that existed at the time, mirrored under a timestamped directory:
+//!
+//! ```text
+//! .aidlc-helper/backups/
+//! └── 20261019-153012/
+//!     ├── backup.json          # when it was taken and which project paths it holds
+//!     └── files/
+//!         └── .kiro/...
+//! ```
+//!
+//! Only the most recent [`MAX_BACKUPS`] are kept. [`restore`] puts a backup back through
+//! the same staging swap as an install, so a failed restore leaves the project untouched.
+
+use crate::staging::Staging;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Backups folder, relative to the project root.
+pub const BACKUPS_DIR: &str = ".aidlc-helper/backups";
+const INDEX_FILE_NAME: &str = "backup.json";
+const FILES_DIR_NAME: &str = "files";
+const MAX_BACKUPS: usize = 10;
+
+/// A snapshot of installed rules.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backup {
+    #[serde(skip)]
+    pub id: String,
+    /// Creation time in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// Project-relative paths held by the backup.
+    pub targets: Vec<PathBuf>,
+    /// Install targets that didn't exist when the backup was taken. Restoring the backup
+    /// removes them. Empty for backups taken before they were recorded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub absent: Vec<PathBuf>,
+}
+
+/// Copy every existing target into a new backup. Returns `None` if none of them exist.
+pub fn snapshot(root: &Path, targets: &[PathBuf]) -> Result<Option<Backup>> {
+    let (existing, absent): (Vec<PathBuf>, Vec<PathBuf>) =
+        targets.iter().cloned().partition(|t| root.join(t).exists());
+    if existing.is_empty() {
+        return Ok(None);
+    }
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let backups = root.join(BACKUPS_DIR);
+    let base_id = format_timestamp(created_at);
+    let mut id = base_id.clone();
+    let mut n = 1;
+    while backups.join(&id).exists() {
+        id = format!("{base_id}-{n}");
+        n += 1;
+    }
+
+    let dir = backups.join(&id);
+    for target in &existing {
+        copy_recursive(&root.join(target), &dir.join(FILES_DIR_NAME).join(target))
+            .with_context(|| format!("Failed to back up {}", target.display()))?;
+    }
+    let backup = Backup {
+        id,
+        created_at,
+        targets: existing,
+        absent,
+    };
+    fs::write(
+        dir.join(INDEX_FILE_NAME),
+        serde_json::to_string_pretty(&backup)?,
+    )?;
+
+    prune(root)?;
+    Ok(Some(backup))
+}
+
+/// List available backups, newest first.
+pub fn list(root: &Path) -> Result<Vec<Backup>> {
+    let backups = root.join(BACKUPS_DIR);
+    if !backups.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut found = Vec::new();
+    for entry in fs::read_dir(&backups)? {
+        let entry = entry?;
+        let index = entry.path().join(INDEX_FILE_NAME);
+        if !index.exists() {
+            continue;
+        }
+        let mut backup: Backup = serde_json::from_str(&fs::read_to_string(&index)?)
+            .with_context(|| format!("Corrupted backup index: {}", index.display()))?;
+        backup.id = entry.file_name().to_string_lossy().to_string();
+        found.push(backup);
+    }
+    // Ids taken within the same second get a `-N` suffix, so compare by length before text.
+    found.sort_by(|a, b| (b.created_at, b.id.len(), &b.id).cmp(&(a.created_at, a.id.len(), &a.id)));
+    Ok(found)
+}
+
+/// Put the backup with the given id back in place of the current rules. The current rules
+/// are backed up first, so a restore can be undone too.
+///
+/// Returns the restored backup and the one taken of the rules it replaced, if any.
+pub fn restore(root: &Path, id: &str) -> Result<(Backup, Option<Backup>)> {
+    let Some(backup) = list(root)?.into_iter().find(|b| b.id == id) else {
+        bail!("No backup named {id:?} — run `restore --list` to see available backups");
+    };
+
+    let files = root.join(BACKUPS_DIR).join(&backup.id).join(FILES_DIR_NAME);
+    let staging = Staging::new(root)?;
+    for target in &backup.targets {
+        copy_recursive(&files.join(target), &staging.root().join(target))
+            .with_context(|| format!("Failed to read {} from backup", target.display()))?;
+    }
+    // Absent targets aren't staged, so committing them removes them.
+    let targets: Vec<PathBuf> = backup
+        .targets
+        .iter()
+        .chain(&backup.absent)
+        .cloned()
+        .collect();
+    // Taken after the backup was staged: pruning may delete the backup being restored.
+    let previous = snapshot(root, &targets)?;
+    staging.commit(&targets)?;
+    Ok((backup, previous))
+}
+
+/// Delete the oldest backups beyond [`MAX_BACKUPS`].
+fn prune(root: &Path) -> Result<()> {
+    for old in list(root)?.iter().skip(MAX_BACKUPS) {
+        fs::remove_dir_all(root.join(BACKUPS_DIR).join(&old.id)).ok();
+    }
+    Ok(())
+}
+
+/// Copy a file, or a directory tree, from `from` to `to`.
+pub fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// Format seconds since the Unix epoch as a UTC `YYYYMMDD-HHMMSS` backup id.
+fn format_timestamp(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) civil date.
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format_timestamp(0), "19700101-000000");
+        assert_eq!(format_timestamp(951_825_600), "20000229-120000");
+        assert_eq!(format_timestamp(1_760_000_000), "20251009-085320");
+    }
+
+    #[test]
+    fn snapshot_skips_when_nothing_installed() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = snapshot(dir.path(), &[PathBuf::from(".kiro/steering/rules")]).unwrap();
+        assert!(backup.is_none());
+        assert!(list(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn snapshot_and_restore_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(&root.join("rules/core-workflow.md"), "customized");
+        write(&root.join("manifest"), "hashes");
+        let targets = [
+            PathBuf::from("rules"),
+            PathBuf::from("details"),
+            PathBuf::from("manifest"),
+        ];
+
+        let backup = snapshot(root, &targets).unwrap().unwrap();
+        assert_eq!(
+            backup.targets,
+            vec![PathBuf::from("rules"), PathBuf::from("manifest")]
+        );
+
+        // Overwrite with a fresh install
+        write(&root.join("rules/core-workflow.md"), "pristine");
+        write(&root.join("rules/extra.md"), "new file");
+        fs::remove_file(root.join("manifest")).unwrap();
+
+        let listed = list(root).unwrap();
+        assert_eq!(listed, vec![backup.clone()]);
+
+        let (restored, previous) = restore(root, &backup.id).unwrap();
+        assert_eq!(restored, backup);
+        assert_eq!(
+            fs::read_to_string(root.join("rules/core-workflow.md")).unwrap(),
+            "customized"
+        );
+        assert!(!root.join("rules/extra.md").exists());
+        assert_eq!(fs::read_to_string(root.join("manifest")).unwrap(), "hashes");
+
+        // The replaced rules were backed up, so the restore can be undone.
+        let previous = previous.unwrap();
+        assert_eq!(previous.targets, vec![PathBuf::from("rules")]);
+        assert_eq!(
+            previous.absent,
+            vec![PathBuf::from("manifest"), PathBuf::from("details")]
+        );
+        restore(root, &previous.id).unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("rules/core-workflow.md")).unwrap(),
+            "pristine"
+        );
+        assert!(!root.join("manifest").exists());
+    }
+
+    #[test]
+    fn restore_rejects_unknown_id() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(restore(dir.path(), "../../etc").is_err());
+    }
+
+    #[test]
+    fn keeps_only_most_recent_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(&root.join("rules/core-workflow.md"), "rules");
+
+        for _ in 0..MAX_BACKUPS + 2 {
+            snapshot(root, &[PathBuf::from("rules")]).unwrap();
+        }
+        assert_eq!(list(root).unwrap().len(), MAX_BACKUPS);
+    }
+}
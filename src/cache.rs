@@ -6,18 +6,71 @@
 //! Cache layout:
 //! ```text
 //! ~/.cache/aidlc-workflows-helper/   (Linux/macOS)
+//! ├── .lock
 //! └── v0.1.1/
 //!     ├── aidlc-rules.zip
+//!     ├── meta.json
 //!     └── sha256
 //! ```
 //!
+//! Release tags come from the GitHub API and are validated before being used as directory
+//! names, so a hostile tag like `../../etc` can't escape the cache root.
+//!
 //! When a new version is detected, old cached versions are automatically cleaned up.
+//!
+//! Several helper processes may share the cache (e.g. a monorepo bootstrap script running
+//! one install per repo in parallel). Download, verification, cleanup and extraction are
+//! serialized with an advisory lock on `<cache root>/.lock` — see [`lock`].
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const CACHE_DIR_NAME: &str = "aidlc-workflows-helper";
+const LOCK_FILE_NAME: &str = ".lock";
+const METADATA_FILE_NAME: &str = "meta.json";
+const MAX_TAG_LEN: usize = 64;
+
+/// Where the checksum of a cached zip came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChecksumSource {
+    /// Computed locally while downloading — the release doesn't publish its own checksum.
+    ComputedOnDownload,
+}
+
+/// Metadata recorded alongside each cached zip (`meta.json`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheMetadata {
+    /// GitHub repository the release was downloaded from, e.g. `awslabs/aidlc-workflows`.
+    pub source_repo: String,
+    pub url: String,
+    /// Size of the zip in bytes.
+    pub size: u64,
+    /// Download time in seconds since the Unix epoch.
+    pub downloaded_at: u64,
+    pub sha256: String,
+    pub checksum_source: ChecksumSource,
+}
+
+impl CacheMetadata {
+    /// Metadata for a zip that was just downloaded from `url`.
+    pub fn new(url: &str, size: u64, sha256: &str) -> Self {
+        Self {
+            source_repo: crate::github::SOURCE_REPO.to_string(),
+            url: url.to_string(),
+            size,
+            downloaded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            sha256: sha256.to_string(),
+            checksum_source: ChecksumSource::ComputedOnDownload,
+        }
+    }
+}
 
 /// Get the platform-appropriate cache directory.
 fn cache_root() -> Result<PathBuf> {
@@ -25,14 +78,36 @@ fn cache_root() -> Result<PathBuf> {
     Ok(base.join(CACHE_DIR_NAME))
 }
 
+/// Reject tags that aren't safe to use as a single directory name.
+///
+/// Only ASCII alphanumerics and `.`, `-`, `_`, `+` are allowed, and the tag may not start
+/// with `.` — this rules out `..`, path separators and hidden files like the lock file.
+fn validate_tag(tag: &str) -> Result<&str> {
+    let valid = !tag.is_empty()
+        && tag.len() <= MAX_TAG_LEN
+        && !tag.starts_with('.')
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+'));
+    if !valid {
+        bail!("Refusing to cache release with unsafe tag {tag:?}");
+    }
+    Ok(tag)
+}
+
+/// Get the cache directory for a given version tag.
+fn tag_dir(tag: &str) -> Result<PathBuf> {
+    Ok(cache_root()?.join(validate_tag(tag)?))
+}
+
 /// Get the path where a zip for a given version tag would be cached.
 pub fn cached_zip_path(tag: &str) -> Result<PathBuf> {
-    Ok(cache_root()?.join(tag).join("aidlc-rules.zip"))
+    Ok(tag_dir(tag)?.join("aidlc-rules.zip"))
 }
 
 /// Get the path where the checksum for a cached zip is stored.
 pub fn cached_checksum_path(tag: &str) -> Result<PathBuf> {
-    Ok(cache_root()?.join(tag).join("sha256"))
+    Ok(tag_dir(tag)?.join("sha256"))
 }
 
 /// Check if a cached zip exists for the given tag.
@@ -60,10 +135,93 @@ pub fn read_checksum(tag: &str) -> Result<Option<String>> {
     }
 }
 
+/// Store metadata alongside the cached zip.
+pub fn store_metadata(tag: &str, metadata: &CacheMetadata) -> Result<()> {
+    write_metadata(&tag_dir(tag)?, metadata)
+}
+
+/// Read stored metadata for a cached version. Returns `None` for entries cached by older
+/// versions of the helper, which only recorded a checksum.
+pub fn read_metadata(tag: &str) -> Result<Option<CacheMetadata>> {
+    read_metadata_in(&tag_dir(tag)?)
+}
+
+/// Testable core: write `meta.json` into a cache entry directory.
+fn write_metadata(dir: &Path, metadata: &CacheMetadata) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let json = serde_json::to_string_pretty(metadata)?;
+    fs::write(dir.join(METADATA_FILE_NAME), json)?;
+    Ok(())
+}
+
+/// Testable core: read `meta.json` from a cache entry directory.
+fn read_metadata_in(dir: &Path) -> Result<Option<CacheMetadata>> {
+    let path = dir.join(METADATA_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)?;
+    let metadata = serde_json::from_str(&content)
+        .with_context(|| format!("Corrupted cache metadata: {}", path.display()))?;
+    Ok(Some(metadata))
+}
+
+/// Exclusive advisory lock on the cache root. Released when dropped.
+pub struct CacheLock {
+    _file: fs::File,
+}
+
+/// Acquire the exclusive cache lock, blocking until it is available.
+///
+/// `on_wait` is called once, before blocking, if another process currently holds the
+/// lock — use it to tell the user why nothing is happening.
+pub fn lock(on_wait: impl FnOnce()) -> Result<CacheLock> {
+    lock_dir(&cache_root()?, on_wait)
+}
+
+/// Testable core: acquire the lock file inside `dir`, blocking if it is held.
+fn lock_dir(dir: &Path, on_wait: impl FnOnce()) -> Result<CacheLock> {
+    let file = open_lock_file(dir)?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(fs::TryLockError::WouldBlock) => {
+            on_wait();
+            file.lock().context("Failed to lock download cache")?;
+        }
+        Err(fs::TryLockError::Error(e)) => {
+            return Err(e).context("Failed to lock download cache");
+        }
+    }
+    Ok(CacheLock { _file: file })
+}
+
+/// Try to acquire the lock file inside `dir` without blocking.
+/// Returns `None` if another handle already holds it.
+#[cfg(test)]
+fn try_lock_dir(dir: &Path) -> Result<Option<CacheLock>> {
+    let file = open_lock_file(dir)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(CacheLock { _file: file })),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(e)) => Err(e).context("Failed to lock download cache"),
+    }
+}
+
+fn open_lock_file(dir: &Path) -> Result<fs::File> {
+    fs::create_dir_all(dir)?;
+    fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE_NAME))
+        .context("Failed to open cache lock file")
+}
+
 /// Delete all cached versions except `keep_tag`.
 ///
 /// Called after downloading a new version to ensure only one version is cached at a time.
-/// Failures to delete individual directories are silently ignored (best-effort cleanup).
+/// Callers must hold the [`CacheLock`] so that no other process is reading a directory
+/// while it is removed. Failures to delete individual directories are silently ignored (best-effort cleanup).
 pub fn cleanup_old_versions(keep_tag: &str) -> Result<()> {
     let root = cache_root()?;
     if !root.exists() {
@@ -80,3 +238,66 @@ pub fn cleanup_old_versions(keep_tag: &str) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_release_tags() {
+        for tag in ["v0.1.1", "1.0.0-rc.1", "v2_beta+build.5"] {
+            assert_eq!(validate_tag(tag).unwrap(), tag);
+        }
+    }
+
+    #[test]
+    fn rejects_unsafe_tags() {
+        for tag in ["", ".", "..", "../x", "a/b", "a\\b", ".lock", "v1\0", "v 1"] {
+            assert!(validate_tag(tag).is_err(), "{tag:?} should be rejected");
+        }
+        assert!(validate_tag(&"v".repeat(MAX_TAG_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn metadata_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = CacheMetadata::new(
+            "https://github.com/awslabs/aidlc-workflows/releases/download/v0.1.1/rules.zip",
+            1234,
+            "abc123",
+        );
+        write_metadata(dir.path(), &metadata).unwrap();
+
+        let read = read_metadata_in(dir.path()).unwrap().unwrap();
+        assert_eq!(read, metadata);
+        assert_eq!(read.source_repo, "awslabs/aidlc-workflows");
+        assert_eq!(read.checksum_source, ChecksumSource::ComputedOnDownload);
+    }
+
+    #[test]
+    fn missing_metadata_returns_none() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_metadata_in(dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let first = try_lock_dir(dir.path()).unwrap();
+        assert!(first.is_some());
+        assert!(try_lock_dir(dir.path()).unwrap().is_none());
+
+        drop(first);
+        assert!(try_lock_dir(dir.path()).unwrap().is_some());
+    }
+
+    #[test]
+    fn lock_dir_does_not_wait_when_free() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut waited = false;
+        let _lock = lock_dir(dir.path(), || waited = true).unwrap();
+        assert!(!waited);
+        assert!(dir.path().join(LOCK_FILE_NAME).exists());
+    }
+}
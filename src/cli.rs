@@ -0,0 +1,89 @@
+//! Command-line arguments.
+//!
+//! Running the binary without a subcommand starts the interactive installation flow.
+//! Subcommands cover maintenance tasks on an existing installation.
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Agent to install rules for, skipping the selection prompt: a built-in id (e.g.
+    /// `kiro`, `cursor`, `agents-md`) or `custom:<rules folder>`. Repeat it to install for
+    /// several agents sharing one copy of the rule details.
+    #[arg(long = "agent", value_name = "ID")]
+    pub agents: Vec<String>,
+    /// Project to work on. Rules, manifests and `.gitignore` entries are relative to it.
+    /// Defaults to the current directory.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub project_root: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Restore rules from a backup taken before an overwrite
+    Restore {
+        /// List available backups instead of restoring one
+        #[arg(long, conflicts_with = "id")]
+        list: bool,
+        /// Backup to restore (prompts for one when omitted)
+        id: Option<String>,
+    },
+    /// Show how locally modified rule files differ from the pristine release
+    Diff {
+        /// Folder holding `aws-aidlc-rule-details/` and the manifest (e.g. `.kiro`).
+        /// Defaults to the folder of every built-in agent that has a manifest.
+        #[arg(long, value_name = "DIR")]
+        details_parent: Option<String>,
+    },
+    /// Check installed rules against the integrity manifest, without prompting or
+    /// downloading.
+    ///
+    /// Exit codes: 0 = clean, 3 = modified or unexpected files, 4 = missing files,
+    /// 5 = no manifest found, 6 = required signature missing or invalid, 1 = any other
+    /// error (2 is reserved for usage errors).
+    Verify {
+        /// Folder holding `aws-aidlc-rule-details/` and the manifest (e.g. `.kiro`).
+        /// Defaults to the folder of every built-in agent that has a manifest.
+        #[arg(long, value_name = "DIR")]
+        details_parent: Option<String>,
+        /// Print a GitHub Actions `::error` annotation for each offending file
+        #[arg(long)]
+        github_annotations: bool,
+    },
+    /// List references in the installed rule files (links, backticked paths) that don't
+    /// resolve to an existing file.
+    ///
+    /// Exit codes: 0 = every reference resolves, 7 = broken references found, 1 = any
+    /// other error (2 is reserved for usage errors).
+    CheckLinks {
+        /// Folder holding `aws-aidlc-rule-details/` and the manifest (e.g. `.kiro`).
+        /// Defaults to the folder of every built-in agent that has a manifest.
+        #[arg(long, value_name = "DIR")]
+        details_parent: Option<String>,
+    },
+    /// Remove installed rules, their integrity manifest and any block added to an agent
+    /// instruction file (e.g. `AGENTS.md`). A backup is kept.
+    Uninstall {
+        /// Folder holding `aws-aidlc-rule-details/` and the manifest (e.g. `.kiro`).
+        /// Defaults to the folder of every built-in agent that has a manifest.
+        #[arg(long, value_name = "DIR")]
+        details_parent: Option<String>,
+    },
+    /// Generate a key pair for signing integrity manifests
+    Keygen {
+        /// Replace existing keys
+        #[arg(long)]
+        force: bool,
+    },
+    /// Sign the integrity manifest of an unmodified installation
+    Sign {
+        /// Folder holding `aws-aidlc-rule-details/` and the manifest (e.g. `.kiro`).
+        /// Defaults to the folder of every built-in agent that has a manifest.
+        #[arg(long, value_name = "DIR")]
+        details_parent: Option<String>,
+    },
+}
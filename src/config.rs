@@ -0,0 +1,185 @@
+//! Project configuration, read from `.aidlc-helper/config.toml`.
+//!
+//! The file is optional and meant to be committed, so the whole team installs rules the
+//! same way. Every setting has a default; unknown keys are rejected to catch typos.
+//!
+//! ```toml
+//! [integrity]
+//! hash_mode = "normalized"                 # or "raw" (default)
+//! require_signature = true                 # default: false
+//! public_key = ".aidlc-helper/signing.pub" # default
+//!
+//! [overlay]
+//! dir = ".aidlc-overlay"                   # default
+//! index = true                             # default: false
+//!
+//! [kiro]
+//! inclusion = "fileMatch"                  # "always" (default), "fileMatch" or "manual"
+//! file_match_pattern = "src/**/*.rs"       # required for "fileMatch"
+//! ```
+
+use crate::agent::Inclusion;
+use crate::integrity::HashMode;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Config file location, relative to the project root.
+pub const CONFIG_PATH: &str = ".aidlc-helper/config.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub integrity: IntegrityConfig,
+    pub overlay: OverlayConfig,
+    pub kiro: KiroConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntegrityConfig {
+    /// How installed files are hashed. `normalized` ignores CRLF/LF differences and a
+    /// UTF-8 BOM, for teams using `core.autocrlf=true`.
+    pub hash_mode: HashMode,
+    /// Treat a missing or invalid manifest signature as a verification failure.
+    pub require_signature: bool,
+    /// Path of the team's Ed25519 public key, relative to the project root.
+    /// Defaults to [`crate::signing::DEFAULT_PUBLIC_KEY_PATH`].
+    pub public_key: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OverlayConfig {
+    /// Overlay folder, relative to the project root. Defaults to
+    /// [`crate::overlay::DEFAULT_OVERLAY_DIR`].
+    pub dir: Option<String>,
+    /// Add a section listing the overlay files to core-workflow.md.
+    pub index: bool,
+}
+
+impl OverlayConfig {
+    /// The overlay folder, relative to `root`, if there is one. The default folder is
+    /// optional, but a configured `dir` that doesn't exist is an error.
+    pub fn existing_dir(&self, root: &Path) -> Result<Option<PathBuf>> {
+        let Some(dir) = &self.dir else {
+            let dir = PathBuf::from(crate::overlay::DEFAULT_OVERLAY_DIR);
+            return Ok(root.join(&dir).is_dir().then_some(dir));
+        };
+        if !root.join(dir).is_dir() {
+            bail!("The overlay folder {dir:?} set in {CONFIG_PATH} doesn't exist");
+        }
+        Ok(Some(PathBuf::from(dir)))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KiroConfig {
+    /// When Kiro loads the core workflow steering file.
+    pub inclusion: Inclusion,
+    /// Files that pull in the core workflow in `fileMatch` mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_match_pattern: Option<String>,
+}
+
+/// Load the project config, falling back to defaults when the file doesn't exist.
+pub fn load(root: &Path) -> Result<Config> {
+    let path = root.join(CONFIG_PATH);
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    let config: Config = toml::from_str(&content)
+        .with_context(|| format!("Invalid config file {}", path.display()))?;
+    if config.kiro.inclusion == Inclusion::FileMatch && config.kiro.file_match_pattern.is_none() {
+        bail!(
+            "Invalid config file {}: [kiro] inclusion = \"fileMatch\" needs a file_match_pattern",
+            path.display()
+        );
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(root: &Path, content: &str) {
+        let path = root.join(CONFIG_PATH);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn missing_file_uses_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = load(dir.path()).unwrap();
+        assert_eq!(config.integrity.hash_mode, HashMode::Raw);
+    }
+
+    #[test]
+    fn reads_hash_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "[integrity]\nhash_mode = \"normalized\"\n");
+        let config = load(dir.path()).unwrap();
+        assert_eq!(config.integrity.hash_mode, HashMode::Normalized);
+    }
+
+    #[test]
+    fn reads_signature_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            "[integrity]\nrequire_signature = true\npublic_key = \"keys/team.pub\"\n",
+        );
+        let config = load(dir.path()).unwrap();
+        assert!(config.integrity.require_signature);
+        assert_eq!(
+            config.integrity.public_key.as_deref(),
+            Some("keys/team.pub")
+        );
+    }
+
+    #[test]
+    fn reads_overlay_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            "[overlay]\ndir = \"team-rules\"\nindex = true\n",
+        );
+        let config = load(dir.path()).unwrap();
+        assert_eq!(config.overlay.dir.as_deref(), Some("team-rules"));
+        assert!(config.overlay.index);
+
+        // A configured folder must exist; the default one is optional.
+        assert!(config.overlay.existing_dir(dir.path()).is_err());
+        fs::create_dir(dir.path().join("team-rules")).unwrap();
+        assert_eq!(
+            config.overlay.existing_dir(dir.path()).unwrap(),
+            Some(PathBuf::from("team-rules"))
+        );
+        assert_eq!(
+            Config::default().overlay.existing_dir(dir.path()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_kiro_inclusion() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "[kiro]\ninclusion = \"manual\"\n");
+        assert_eq!(load(dir.path()).unwrap().kiro.inclusion, Inclusion::Manual);
+
+        write_config(dir.path(), "[kiro]\ninclusion = \"fileMatch\"\n");
+        assert!(load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "[integrity]\nhash_mod = \"raw\"\n");
+        assert!(load(dir.path()).is_err());
+    }
+}
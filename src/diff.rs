@@ -0,0 +1,226 @@
+//! Diffs of installed rule files against a pristine copy of their release.
+//!
+//! The pristine copy is rebuilt by running the install pipeline again on the cached
+//! release zip, with the folders and commit workflow recorded in the integrity manifest,
+//! inside a staging directory. The overlay and patches are read from the project as they
+//! are now, so every rebuilt file is checked against its hash in the manifest: one that
+//! doesn't match — typically an overlay file edited since the install — has no pristine
+//! copy to diff against.
+
+use crate::install::{self, InstallPlan};
+use crate::integrity;
+use crate::staging::Staging;
+use anyhow::Result;
+use console::style;
+use similar::TextDiff;
+use std::fs;
+use std::path::Path;
+
+/// A flagged file's pristine and current content. A missing side is empty.
+pub struct FileDiff {
+    /// Path relative to the project root.
+    pub path: String,
+    /// `None` when the rebuilt file doesn't match the hash in the manifest.
+    pub pristine: Option<String>,
+    pub current: String,
+}
+
+/// Rebuild `plan` from `zip_path` and pair every modified, missing or unexpected file with
+/// its pristine content. Returns nothing when the installation is unchanged.
+pub fn collect(root: &Path, plan: &InstallPlan, zip_path: &Path) -> Result<Vec<FileDiff>> {
+    let Some(manifest) = integrity::read_manifest(root, plan.details_parent)? else {
+        return Ok(Vec::new());
+    };
+    let Some(report) = integrity::verify_manifest(root, plan.details_parent)? else {
+        return Ok(Vec::new());
+    };
+    if report.is_clean() {
+        return Ok(Vec::new());
+    }
+
+    let staging = Staging::new(root)?;
+    install::build(zip_path, &staging.root(), plan)?;
+
+    let installed = report.modified.iter().chain(&report.missing).map(|path| {
+        let entry = manifest.files.iter().find(|entry| entry.path == *path);
+        let rebuilt = fs::read(staging.root().join(path)).ok();
+        let pristine = rebuilt
+            .filter(|bytes| entry.is_some_and(|entry| integrity::hash_bytes(bytes) == entry.hash))
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+        Ok(FileDiff {
+            path: path.clone(),
+            pristine,
+            current: read_or_empty(&root.join(path))?,
+        })
+    });
+    // Files the manifest doesn't list were never installed, whatever the rebuild holds.
+    let unexpected = report.unexpected.iter().map(|path| {
+        Ok(FileDiff {
+            path: path.clone(),
+            pristine: Some(String::new()),
+            current: read_or_empty(&root.join(path))?,
+        })
+    });
+    installed.chain(unexpected).collect()
+}
+
+/// Render a unified diff, with `a/` the pristine file and `b/` the installed one. Without
+/// a pristine copy, only says that the file differs from the manifest.
+pub fn unified(diff: &FileDiff) -> String {
+    let Some(pristine) = &diff.pristine else {
+        return format!(
+            "{} differs from the manifest, and its pristine copy can't be rebuilt (was its overlay source changed since the install?)\n",
+            diff.path
+        );
+    };
+    TextDiff::from_lines(pristine, &diff.current)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", diff.path), &format!("b/{}", diff.path))
+        .to_string()
+}
+
+/// Print a unified diff with added lines in green, removed lines in red and hunk headers
+/// in cyan.
+pub fn print_colored(diff: &FileDiff) {
+    for line in unified(diff).lines() {
+        let styled = if line.starts_with("+++") || line.starts_with("---") {
+            style(line).bold()
+        } else if line.starts_with('+') {
+            style(line).green()
+        } else if line.starts_with('-') {
+            style(line).red()
+        } else if line.starts_with("@@") {
+            style(line).cyan()
+        } else {
+            style(line)
+        };
+        println!("    {styled}");
+    }
+}
+
+fn read_or_empty(path: &Path) -> Result<String> {
+    if !path.is_file() {
+        return Ok(String::new());
+    }
+    Ok(String::from_utf8_lossy(&fs::read(path)?).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::HashMode;
+    use crate::prompt::CommitWorkflow;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn create_test_zip(dir: &Path) -> PathBuf {
+        let zip_path = dir.join("test.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("aidlc-rules/aws-aidlc-rules/core-workflow.md", options)
+            .unwrap();
+        zip.write_all(b"# Core Workflow\n").unwrap();
+        zip.start_file("aidlc-rules/aws-aidlc-rule-details/common/a.md", options)
+            .unwrap();
+        zip.write_all(b"line 1\nline 2\nline 3\n").unwrap();
+        zip.finish().unwrap();
+        zip_path
+    }
+
+    /// An unsigned, unpatched install of release v1 into `.kiro`.
+    fn plan(kiro: &crate::agent::Kiro) -> InstallPlan<'_> {
+        InstallPlan {
+            agents: vec![kiro],
+            details_parent: ".kiro",
+            commit_workflow: &CommitWorkflow::None,
+            release: "v1",
+            hash_mode: HashMode::Raw,
+            signing_key: None,
+            patches: &[],
+            overlay_dir: None,
+            overlay_index: false,
+        }
+    }
+
+    #[test]
+    fn diffs_modified_files_against_pristine_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_test_zip(dir.path());
+        let root = dir.path().join("project");
+        fs::create_dir_all(&root).unwrap();
+        let kiro = crate::agent::Kiro::default();
+        let plan = InstallPlan {
+            commit_workflow: &CommitWorkflow::Conventional,
+            ..plan(&kiro)
+        };
+        install::build(&zip_path, &root, &plan).unwrap();
+
+        let file = ".kiro/aws-aidlc-rule-details/common/a.md";
+        fs::write(root.join(file), "line 1\nline two\nline 3\n").unwrap();
+
+        let diffs = collect(&root, &plan, &zip_path).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, file);
+        assert_eq!(
+            diffs[0].pristine.as_deref(),
+            Some("line 1\nline 2\nline 3\n")
+        );
+
+        let rendered = unified(&diffs[0]);
+        assert!(rendered.contains(&format!("--- a/{file}")));
+        assert!(rendered.contains("-line 2\n"));
+        assert!(rendered.contains("+line two\n"));
+    }
+
+    #[test]
+    fn unchanged_install_has_no_diffs() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_test_zip(dir.path());
+        let root = dir.path().join("project");
+        fs::create_dir_all(&root).unwrap();
+        let kiro = crate::agent::Kiro::default();
+        let plan = plan(&kiro);
+        install::build(&zip_path, &root, &plan).unwrap();
+
+        assert!(collect(&root, &plan, &zip_path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn overlay_file_changed_since_install_has_no_pristine_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_test_zip(dir.path());
+        let root = dir.path().join("project");
+        let overlay = dir.path().join("overlay");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(overlay.join("common")).unwrap();
+        fs::write(overlay.join("common/team.md"), "team v1\n").unwrap();
+        let kiro = crate::agent::Kiro::default();
+        let plan = InstallPlan {
+            overlay_dir: Some(&overlay),
+            ..plan(&kiro)
+        };
+        install::build(&zip_path, &root, &plan).unwrap();
+
+        let file = ".kiro/aws-aidlc-rule-details/common/team.md";
+        fs::write(root.join(file), "team edited\n").unwrap();
+        let diffs = collect(&root, &plan, &zip_path).unwrap();
+        assert_eq!(diffs[0].pristine.as_deref(), Some("team v1\n"));
+
+        fs::write(overlay.join("common/team.md"), "team v2\n").unwrap();
+        let diffs = collect(&root, &plan, &zip_path).unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].pristine, None);
+        assert!(unified(&diffs[0]).contains("can't be rebuilt"));
+    }
+
+    #[test]
+    fn missing_file_diffs_as_full_deletion() {
+        let diff = FileDiff {
+            path: "rules/core-workflow.md".to_string(),
+            pristine: Some("# Core\n".to_string()),
+            current: String::new(),
+        };
+        assert!(unified(&diff).contains("-# Core\n"));
+    }
+}
@@ -41,10 +41,20 @@ pub fn download_to(client: &reqwest::blocking::Client, url: &str, dest: &Path) -
 
 /// Verify that a file's SHA-256 hash matches `expected`.
 ///
-/// If the hash doesn't match, the file is deleted and an error is returned.
-/// This prevents using corrupted or tampered downloads.
-pub fn verify_checksum(path: &Path, expected: &str) -> Result<()> {
+/// When `expected_size` is known (from the cache metadata) it is checked first, which
+/// gives a clearer error for truncated files. If either check fails, the file is deleted
+/// and an error is returned. This prevents using corrupted or tampered downloads.
+pub fn verify_checksum(path: &Path, expected: &str, expected_size: Option<u64>) -> Result<()> {
     let bytes = fs::read(path).context("Failed to read file for checksum")?;
+    if let Some(size) = expected_size
+        && bytes.len() as u64 != size
+    {
+        fs::remove_file(path).ok();
+        bail!(
+            "Size mismatch!\n  Expected: {size} bytes\n  Got:      {} bytes\n\nCorrupted file has been deleted.",
+            bytes.len()
+        );
+    }
     let mut hasher = Sha256::new();
     hasher.update(&bytes);
     let actual = format!("{:x}", hasher.finalize());
@@ -70,7 +80,7 @@ mod tests {
         fs::write(&file, b"hello world").unwrap();
 
         let expected = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
-        verify_checksum(&file, expected).unwrap();
+        verify_checksum(&file, expected, Some(11)).unwrap();
     }
 
     #[test]
@@ -82,8 +92,21 @@ mod tests {
         let result = verify_checksum(
             &file,
             "0000000000000000000000000000000000000000000000000000000000000000",
+            None,
         );
         assert!(result.is_err());
         assert!(!file.exists(), "corrupted file should be deleted");
     }
+
+    #[test]
+    fn verify_checksum_fails_on_size_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("test.bin");
+        fs::write(&file, b"hello").unwrap();
+
+        let expected = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+        let err = verify_checksum(&file, expected, Some(11)).unwrap_err();
+        assert!(err.to_string().contains("Size mismatch"));
+        assert!(!file.exists(), "truncated file should be deleted");
+    }
 }
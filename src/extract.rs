@@ -6,62 +6,246 @@
 //!
 //! - `<rules_folder>/rules/` — e.g. `.kiro/steering/rules/core-workflow.md`
 //! - `<details_parent>/aws-aidlc-rule-details/` — e.g. `.kiro/aws-aidlc-rule-details/`
+//!
+//! The archive is treated as untrusted input: entry paths are normalized and rejected if
+//! they escape their destination, symlink entries are refused, and entry count, file size,
+//! total size and compression ratio are capped to defuse zip bombs. Each violation is a
+//! distinct [`ExtractError`].
 
 use anyhow::{Context, Result};
+use std::fmt;
 use std::fs;
-use std::io;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 
+/// Caps applied while extracting. Real releases are a few hundred KB of markdown, so these
+/// leave plenty of headroom while still stopping a hostile archive early.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    max_entries: usize,
+    max_file_size: u64,
+    max_total_size: u64,
+    max_compression_ratio: u64,
+}
+
+const DEFAULT_LIMITS: Limits = Limits {
+    max_entries: 1_000,
+    max_file_size: 5 * 1024 * 1024,
+    max_total_size: 50 * 1024 * 1024,
+    max_compression_ratio: 100,
+};
+
+/// Reasons a release zip is rejected as unsafe to extract.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExtractError {
+    /// An entry path would resolve outside its destination folder.
+    PathTraversal(String),
+    /// The archive contains a symbolic link.
+    Symlink(String),
+    TooManyEntries {
+        count: usize,
+        limit: usize,
+    },
+    FileTooLarge {
+        name: String,
+        limit: u64,
+    },
+    TotalSizeExceeded {
+        limit: u64,
+    },
+    CompressionRatio {
+        name: String,
+        ratio: u64,
+        limit: u64,
+    },
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PathTraversal(name) => {
+                write!(f, "Zip entry escapes its destination folder: {name}")
+            }
+            Self::Symlink(name) => write!(f, "Zip entry is a symbolic link: {name}"),
+            Self::TooManyEntries { count, limit } => {
+                write!(f, "Zip has {count} entries (limit: {limit})")
+            }
+            Self::FileTooLarge { name, limit } => {
+                write!(f, "Zip entry {name} is larger than {limit} bytes")
+            }
+            Self::TotalSizeExceeded { limit } => {
+                write!(f, "Zip contents are larger than {limit} bytes in total")
+            }
+            Self::CompressionRatio { name, ratio, limit } => write!(
+                f,
+                "Zip entry {name} has a suspicious compression ratio of {ratio}:1 (limit: {limit}:1)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {}
+
 /// Extract the zip and install rules into the target folders.
 ///
 /// `aws-aidlc-rules/` from the zip is installed as `<rules_folder>/rules/` (renamed).
 /// `aws-aidlc-rule-details/` is installed as `<details_parent>/aws-aidlc-rule-details/`.
 ///
-/// Returns list of installed file paths relative to the project root.
+/// Both folders are relative to `root` — the project root, or a staging directory that
+/// mirrors it. Returns list of installed file paths relative to `root`. Fails with an
+/// [`ExtractError`] if the archive is unsafe.
 pub fn extract_and_install(
     zip_path: &Path,
+    root: &Path,
     rules_folder: &str,
     details_parent: &str,
+) -> Result<Vec<PathBuf>> {
+    extract_with_limits(zip_path, root, rules_folder, details_parent, DEFAULT_LIMITS)
+}
+
+fn extract_with_limits(
+    zip_path: &Path,
+    root: &Path,
+    rules_folder: &str,
+    details_parent: &str,
+    limits: Limits,
 ) -> Result<Vec<PathBuf>> {
     let file = fs::File::open(zip_path).context("Failed to open zip file")?;
     let mut archive = zip::ZipArchive::new(file).context("Failed to read zip archive")?;
 
+    if archive.len() > limits.max_entries {
+        return Err(ExtractError::TooManyEntries {
+            count: archive.len(),
+            limit: limits.max_entries,
+        }
+        .into());
+    }
+
+    let rules_dest = PathBuf::from(rules_folder).join("rules");
+    let details_dest = PathBuf::from(details_parent).join("aws-aidlc-rule-details");
+
     let mut installed = Vec::new();
+    let mut total_size = 0;
 
     for i in 0..archive.len() {
         let mut entry = archive.by_index(i)?;
         let entry_path = entry.name().to_string();
 
         // aws-aidlc-rules/ from zip → rules/ in target
-        if let Some(rel) = entry_path.strip_prefix("aidlc-rules/aws-aidlc-rules/") {
-            if rel.is_empty() || entry.is_dir() {
-                continue;
-            }
-            let dest = PathBuf::from(rules_folder).join("rules").join(rel);
-            write_entry(&mut entry, &dest)?;
-            installed.push(dest);
+        let (dest_root, rel) = if let Some(rel) =
+            entry_path.strip_prefix("aidlc-rules/aws-aidlc-rules/")
+        {
+            (&rules_dest, rel)
         } else if let Some(rel) = entry_path.strip_prefix("aidlc-rules/aws-aidlc-rule-details/") {
-            if rel.is_empty() || entry.is_dir() {
-                continue;
+            (&details_dest, rel)
+        } else {
+            continue;
+        };
+
+        let rel = normalize_entry_path(rel)
+            .ok_or_else(|| ExtractError::PathTraversal(entry_path.clone()))?;
+        if entry.is_symlink() {
+            return Err(ExtractError::Symlink(entry_path).into());
+        }
+        if rel.as_os_str().is_empty() || entry.is_dir() {
+            continue;
+        }
+        check_declared_size(&entry, &entry_path, limits)?;
+
+        let dest = dest_root.join(rel);
+        let compressed_size = entry.compressed_size().max(1);
+        let max_size = limits
+            .max_file_size
+            .min(compressed_size.saturating_mul(limits.max_compression_ratio));
+        let written = write_entry(&mut entry, &root.join(&dest), max_size)?;
+        if written > limits.max_file_size {
+            return Err(ExtractError::FileTooLarge {
+                name: entry_path,
+                limit: limits.max_file_size,
             }
-            let dest = PathBuf::from(details_parent)
-                .join("aws-aidlc-rule-details")
-                .join(rel);
-            write_entry(&mut entry, &dest)?;
-            installed.push(dest);
+            .into());
         }
+        let ratio = written.div_ceil(compressed_size);
+        if ratio > limits.max_compression_ratio {
+            return Err(ExtractError::CompressionRatio {
+                name: entry_path,
+                ratio,
+                limit: limits.max_compression_ratio,
+            }
+            .into());
+        }
+        total_size += written;
+        if total_size > limits.max_total_size {
+            return Err(ExtractError::TotalSizeExceeded {
+                limit: limits.max_total_size,
+            }
+            .into());
+        }
+        installed.push(dest);
     }
 
     Ok(installed)
 }
 
-fn write_entry(entry: &mut zip::read::ZipFile<impl std::io::Read>, dest: &Path) -> Result<()> {
+/// Normalize a `/`-separated entry path, resolving `.` and `..` components.
+///
+/// Returns `None` if the path is absolute, contains Windows separators or drive prefixes,
+/// or climbs above its starting point.
+fn normalize_entry_path(rel: &str) -> Option<PathBuf> {
+    if rel.starts_with('/') {
+        return None;
+    }
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rel.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            p if p.contains(['\\', ':', '\0']) => return None,
+            p => parts.push(p),
+        }
+    }
+    Some(parts.iter().collect())
+}
+
+/// Reject entries whose header already announces an oversized or bomb-like payload.
+/// Actual byte counts are enforced again while writing, since headers can lie.
+fn check_declared_size(
+    entry: &zip::read::ZipFile<impl Read>,
+    name: &str,
+    limits: Limits,
+) -> Result<(), ExtractError> {
+    let size = entry.size();
+    if size > limits.max_file_size {
+        return Err(ExtractError::FileTooLarge {
+            name: name.to_string(),
+            limit: limits.max_file_size,
+        });
+    }
+    let ratio = size / entry.compressed_size().max(1);
+    if ratio > limits.max_compression_ratio {
+        return Err(ExtractError::CompressionRatio {
+            name: name.to_string(),
+            ratio,
+            limit: limits.max_compression_ratio,
+        });
+    }
+    Ok(())
+}
+
+/// Write at most `max_size + 1` bytes of `entry` to `dest` and return the number written,
+/// so the caller can tell whether the entry exceeded `max_size`.
+fn write_entry(
+    entry: &mut zip::read::ZipFile<impl Read>,
+    dest: &Path,
+    max_size: u64,
+) -> Result<u64> {
     if let Some(parent) = dest.parent() {
         fs::create_dir_all(parent)?;
     }
     let mut out = fs::File::create(dest)?;
-    io::copy(entry, &mut out)?;
-    Ok(())
+    Ok(io::copy(&mut entry.take(max_size + 1), &mut out)?)
 }
 
 /// Check if rules already exist in the target folder.
@@ -76,6 +260,26 @@ pub fn rules_exist(rules_folder: &str, details_parent: &str) -> bool {
 mod tests {
     use super::*;
     use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    /// Create a zip with the given `(name, contents)` entries.
+    fn create_zip(dir: &Path, entries: &[(&str, &[u8])], options: SimpleFileOptions) -> PathBuf {
+        let zip_path = dir.join("crafted.zip");
+        let file = fs::File::create(&zip_path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        for (name, contents) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap();
+        zip_path
+    }
+
+    /// Run extraction into `dir` and return the typed error it failed with.
+    fn extract_err(dir: &Path, zip_path: &Path, limits: Limits) -> ExtractError {
+        let err = extract_with_limits(zip_path, dir, "steering", "kiro", limits).unwrap_err();
+        err.downcast::<ExtractError>().unwrap()
+    }
 
     /// Create a test zip with the expected aidlc-rules structure.
     fn create_test_zip(dir: &Path) -> PathBuf {
@@ -107,14 +311,15 @@ mod tests {
         let rules_folder = dir.path().join("steering");
         let details_parent = dir.path().join("kiro");
 
-        let installed = extract_and_install(
-            &zip_path,
-            &rules_folder.to_string_lossy(),
-            &details_parent.to_string_lossy(),
-        )
-        .unwrap();
+        let installed = extract_and_install(&zip_path, dir.path(), "steering", "kiro").unwrap();
 
-        assert_eq!(installed.len(), 2);
+        assert_eq!(
+            installed,
+            vec![
+                PathBuf::from("steering/rules/core-workflow.md"),
+                PathBuf::from("kiro/aws-aidlc-rule-details/common/process-overview.md"),
+            ]
+        );
 
         // aws-aidlc-rules/ from zip becomes rules/ in target
         let workflow = rules_folder.join("rules/core-workflow.md");
@@ -126,6 +331,157 @@ mod tests {
         assert_eq!(fs::read_to_string(&overview).unwrap(), "# Process Overview");
     }
 
+    #[test]
+    fn rejects_path_traversal() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_zip(
+            dir.path(),
+            &[("aidlc-rules/aws-aidlc-rules/../../../.bashrc", b"evil")],
+            SimpleFileOptions::default(),
+        );
+
+        let err = extract_err(dir.path(), &zip_path, DEFAULT_LIMITS);
+        assert!(matches!(err, ExtractError::PathTraversal(_)));
+        assert!(!dir.path().join(".bashrc").exists());
+    }
+
+    #[test]
+    fn normalizes_entry_paths() {
+        assert_eq!(
+            normalize_entry_path("common/./x/../foo.md").unwrap(),
+            PathBuf::from("common/foo.md")
+        );
+        assert!(normalize_entry_path("../foo.md").is_none());
+        assert!(normalize_entry_path("common/../../foo.md").is_none());
+        assert!(normalize_entry_path("/etc/passwd").is_none());
+        assert!(normalize_entry_path("..\\..\\foo.md").is_none());
+        assert!(normalize_entry_path("C:/foo.md").is_none());
+    }
+
+    #[test]
+    fn rejects_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("symlink.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        zip.add_symlink(
+            "aidlc-rules/aws-aidlc-rule-details/common/link.md",
+            "/etc/passwd",
+            SimpleFileOptions::default(),
+        )
+        .unwrap();
+        zip.finish().unwrap();
+
+        let err = extract_err(dir.path(), &zip_path, DEFAULT_LIMITS);
+        assert!(matches!(err, ExtractError::Symlink(_)));
+    }
+
+    #[test]
+    fn rejects_too_many_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_zip(
+            dir.path(),
+            &[
+                ("aidlc-rules/aws-aidlc-rule-details/a.md", b"a"),
+                ("aidlc-rules/aws-aidlc-rule-details/b.md", b"b"),
+                ("aidlc-rules/aws-aidlc-rule-details/c.md", b"c"),
+            ],
+            SimpleFileOptions::default(),
+        );
+        let limits = Limits {
+            max_entries: 2,
+            ..DEFAULT_LIMITS
+        };
+
+        let err = extract_err(dir.path(), &zip_path, limits);
+        assert_eq!(err, ExtractError::TooManyEntries { count: 3, limit: 2 });
+    }
+
+    #[test]
+    fn rejects_oversized_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_zip(
+            dir.path(),
+            &[("aidlc-rules/aws-aidlc-rule-details/big.md", &[b'x'; 2048])],
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored),
+        );
+        let limits = Limits {
+            max_file_size: 1024,
+            ..DEFAULT_LIMITS
+        };
+
+        let err = extract_err(dir.path(), &zip_path, limits);
+        assert!(matches!(
+            err,
+            ExtractError::FileTooLarge { limit: 1024, .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_total() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_zip(
+            dir.path(),
+            &[
+                ("aidlc-rules/aws-aidlc-rule-details/a.md", &[b'a'; 600]),
+                ("aidlc-rules/aws-aidlc-rule-details/b.md", &[b'b'; 600]),
+            ],
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored),
+        );
+        let limits = Limits {
+            max_total_size: 1000,
+            ..DEFAULT_LIMITS
+        };
+
+        let err = extract_err(dir.path(), &zip_path, limits);
+        assert_eq!(err, ExtractError::TotalSizeExceeded { limit: 1000 });
+    }
+
+    #[test]
+    fn rejects_zip_bomb_compression_ratio() {
+        let dir = tempfile::tempdir().unwrap();
+        let zeros = vec![0u8; 1024 * 1024];
+        let zip_path = create_zip(
+            dir.path(),
+            &[("aidlc-rules/aws-aidlc-rule-details/bomb.md", &zeros)],
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated),
+        );
+
+        let err = extract_err(dir.path(), &zip_path, DEFAULT_LIMITS);
+        assert!(matches!(
+            err,
+            ExtractError::CompressionRatio { limit: 100, .. }
+        ));
+    }
+
+    #[test]
+    fn compression_ratio_is_checked_against_the_bytes_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let zeros = vec![0u8; 1024 * 1024];
+        let zip_path = create_zip(
+            dir.path(),
+            &[("aidlc-rules/aws-aidlc-rule-details/bomb.md", &zeros)],
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated),
+        );
+        // Declare a 1-byte uncompressed size in the local and central headers, so the
+        // declared ratio looks harmless.
+        let mut bytes = fs::read(&zip_path).unwrap();
+        for (signature, offset) in [(b"PK\x03\x04", 22), (b"PK\x01\x02", 24)] {
+            let header = bytes.windows(4).position(|w| w == signature).unwrap();
+            bytes[header + offset..header + offset + 4].copy_from_slice(&1u32.to_le_bytes());
+        }
+        fs::write(&zip_path, bytes).unwrap();
+
+        let err = extract_err(dir.path(), &zip_path, DEFAULT_LIMITS);
+        assert!(matches!(
+            err,
+            ExtractError::CompressionRatio { limit: 100, .. }
+        ));
+        let written = fs::metadata(dir.path().join("kiro/aws-aidlc-rule-details/bomb.md"))
+            .unwrap()
+            .len();
+        assert!(written < zeros.len() as u64);
+    }
+
     #[test]
     fn rules_exist_detects_existing_folders() {
         let dir = tempfile::tempdir().unwrap();
@@ -7,7 +7,8 @@
 use anyhow::{Context, Result, bail};
 use serde::Deserialize;
 
-const API_URL: &str = "https://api.github.com/repos/awslabs/aidlc-workflows/releases/latest";
+/// GitHub repository that AI-DLC releases are downloaded from.
+pub const SOURCE_REPO: &str = "awslabs/aidlc-workflows";
 
 #[derive(Debug, Deserialize)]
 struct Asset {
@@ -33,7 +34,9 @@ pub struct ReleaseInfo {
 /// point to `https://github.com/awslabs/aidlc-workflows/` to prevent supply-chain attacks.
 pub fn fetch_latest_release(client: &reqwest::blocking::Client) -> Result<ReleaseInfo> {
     let release: Release = client
-        .get(API_URL)
+        .get(format!(
+            "https://api.github.com/repos/{SOURCE_REPO}/releases/latest"
+        ))
         .header("User-Agent", "aidlc-workflows-helper")
         .header("Accept", "application/vnd.github+json")
         .send()
@@ -52,7 +55,7 @@ pub fn fetch_latest_release(client: &reqwest::blocking::Client) -> Result<Releas
     // Validate trusted source
     if !zip_asset
         .browser_download_url
-        .starts_with("https://github.com/awslabs/aidlc-workflows/")
+        .starts_with(&format!("https://github.com/{SOURCE_REPO}/"))
     {
         bail!("Untrusted download URL: {}", zip_asset.browser_download_url);
     }
@@ -9,7 +9,13 @@ use std::path::Path;
 
 /// Add an entry to .gitignore if not already present. Creates the file if missing.
 pub fn add_to_gitignore(entry: &str) -> Result<()> {
-    add_entry(Path::new(".gitignore"), entry)
+    add_to_gitignore_in(Path::new("."), entry)
+}
+
+/// Like [`add_to_gitignore`], for the `.gitignore` in `root` rather than the current
+/// directory.
+pub fn add_to_gitignore_in(root: &Path, entry: &str) -> Result<()> {
+    add_entry(&root.join(".gitignore"), entry)
 }
 
 /// Testable core: add entry to a gitignore file at the given path.
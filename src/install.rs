@@ -0,0 +1,433 @@
+//! Builds a complete rules installation under a given root.
+//!
+//! Every step that writes rule files — extraction, the team overlay, path patching, the
+//! relative-paths rule, the commit workflow section, the overlay index, user-defined
+//! patches, the agent's naming and front matter, and the integrity manifest — runs here,
+//! against a staging root rather than the project. See [`crate::staging`] for how the
+//! result is swapped in.
+
+use crate::agent::{self, AgentAdapter};
+use crate::integrity::{HashMode, Manifest};
+use crate::patchfile::{self, Operation};
+use crate::prompt::CommitWorkflow;
+use crate::{extract, integrity, overlay, patch, signing};
+use anyhow::{Context, Result, bail};
+use ed25519_dalek::SigningKey;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The user's choices that determine what gets installed.
+pub struct InstallPlan<'a> {
+    /// Agents the rules are installed for, in selection order. Each gets its own copy of
+    /// the core workflow, in its own folder and format.
+    pub agents: Vec<&'a dyn AgentAdapter>,
+    /// Folder holding the one `aws-aidlc-rule-details/` tree shared by all agents, and
+    /// the integrity manifest. See [`agent::shared_details_parent`].
+    pub details_parent: &'a str,
+    pub commit_workflow: &'a CommitWorkflow,
+    /// Release tag being installed, recorded in the integrity manifest.
+    pub release: &'a str,
+    /// How installed files are hashed in the integrity manifest.
+    pub hash_mode: HashMode,
+    /// Key to sign the integrity manifest with, if one is available.
+    pub signing_key: Option<&'a SigningKey>,
+    /// User-defined patches, applied after the built-in ones.
+    pub patches: &'a [Operation],
+    /// Team overlay folder merged into the details tree, if any.
+    pub overlay_dir: Option<&'a Path>,
+    /// Whether to list the overlay files in core-workflow.md.
+    pub overlay_index: bool,
+}
+
+/// What [`build`] installed.
+pub struct BuildOutput {
+    /// Installed files, relative to the build root.
+    pub files: Vec<PathBuf>,
+    /// Installed files whose rule-details path references were rewritten.
+    pub patched_files: Vec<PathBuf>,
+    /// Files copied from the overlay, relative to the build root.
+    pub overlay_files: Vec<PathBuf>,
+}
+
+impl<'a> InstallPlan<'a> {
+    /// The install choices recorded in `manifest`, or `None` if it predates them.
+    /// `agents` should come from [`Manifest::agents`]. The manifest only records a
+    /// fingerprint of the user-defined patches (see [`Manifest::patches`]), so the plan has
+    /// none; callers that rebuild the install must set them and check the fingerprint
+    /// still matches, as `print_diffs` does.
+    pub fn from_manifest(
+        manifest: &'a Manifest,
+        details_parent: &'a str,
+        agents: &'a [Box<dyn AgentAdapter>],
+    ) -> Option<Self> {
+        Some(InstallPlan {
+            agents: agents.iter().map(|a| a.as_ref()).collect(),
+            details_parent,
+            commit_workflow: manifest.commit_workflow.as_ref()?,
+            release: manifest.release.as_deref()?,
+            hash_mode: if manifest
+                .files
+                .iter()
+                .any(|f| f.mode == HashMode::Normalized)
+            {
+                HashMode::Normalized
+            } else {
+                HashMode::Raw
+            },
+            signing_key: None,
+            patches: &[],
+            overlay_dir: None,
+            overlay_index: false,
+        })
+    }
+
+    /// Directories fully owned by the installation, relative to the project root.
+    pub fn tracked_dirs(&self) -> Vec<PathBuf> {
+        tracked_dirs(&self.agents, self.details_parent)
+    }
+
+    /// Paths owned by the installation, relative to the project root. See [`targets`].
+    pub fn targets(&self) -> Vec<PathBuf> {
+        targets(&self.agents, self.details_parent)
+    }
+}
+
+/// Directories fully owned by an installation for `agents`, relative to the project root:
+/// each agent's `rules/` folder and the shared details tree.
+pub fn tracked_dirs(agents: &[&dyn AgentAdapter], details_parent: &str) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for agent in agents {
+        let rules = Path::new(agent.rules_folder()).join("rules");
+        if !dirs.contains(&rules) {
+            dirs.push(rules);
+        }
+    }
+    dirs.push(Path::new(details_parent).join("aws-aidlc-rule-details"));
+    dirs
+}
+
+/// Paths owned by an installation for `agents`, relative to the project root.
+/// Each one is replaced as a whole when a staged install is committed. Entry files are
+/// included: they are copied into staging before the build (see
+/// [`crate::staging::Staging::copy_in`]) so only the helper's block changes.
+pub fn targets(agents: &[&dyn AgentAdapter], details_parent: &str) -> Vec<PathBuf> {
+    let mut targets = tracked_dirs(agents, details_parent);
+    for agent in agents {
+        let core_workflow = agent.core_workflow_path();
+        if !targets.iter().any(|t| core_workflow.starts_with(t)) {
+            targets.push(core_workflow);
+        }
+        if let Some(entry_file) = agent.entry_file()
+            && !targets.contains(&entry_file)
+        {
+            targets.push(entry_file);
+        }
+    }
+    let manifest = integrity::manifest_path(details_parent);
+    targets.push(signing::signature_path(&manifest));
+    targets.push(manifest);
+    targets
+}
+
+/// Extract and patch the release zip under `root`, then write the integrity manifest and
+/// sign it if the plan has a key.
+///
+/// The release is extracted once, for the first agent; the other agents get copies of
+/// its `rules/` folder, so every agent shares the one details tree.
+///
+/// Returns the installed files, relative to `root`, and those whose paths were patched.
+pub fn build(zip_path: &Path, root: &Path, plan: &InstallPlan) -> Result<BuildOutput> {
+    let mut rules_folders: Vec<&str> = Vec::new();
+    for agent in &plan.agents {
+        if !rules_folders.contains(&agent.rules_folder()) {
+            rules_folders.push(agent.rules_folder());
+        }
+    }
+    let Some((first, others)) = rules_folders.split_first() else {
+        bail!("No agent to install for");
+    };
+    let mut installed = extract::extract_and_install(zip_path, root, first, plan.details_parent)?;
+    copy_rules(root, first, others, &mut installed)?;
+    let overlay_files = match plan.overlay_dir {
+        Some(dir) => overlay::apply(dir, root, plan.details_parent)?,
+        None => Vec::new(),
+    };
+    for file in &overlay_files {
+        if !installed.contains(file) {
+            installed.push(file.clone());
+        }
+    }
+    let mut patched_files = Vec::new();
+    for agent in &plan.agents {
+        for file in agent.rewrite_paths(root, &installed, plan.details_parent)? {
+            if !patched_files.contains(&file) {
+                patched_files.push(file);
+            }
+        }
+    }
+    for rules_folder in &rules_folders {
+        patch::patch_relative_paths_rule(root, rules_folder)?;
+        patch::patch_commit_workflow(root, rules_folder, plan.commit_workflow)?;
+        if plan.overlay_index && !overlay_files.is_empty() {
+            patch::patch_overlay_index(root, rules_folder, &overlay_files)?;
+        }
+    }
+    patchfile::apply(root, plan.patches, &rules_folders, plan.details_parent)?;
+    for agent in &plan.agents {
+        agent::finish(*agent, root, &mut installed, plan.agents.len() > 1)?;
+        agent::write_entry_block(*agent, root, plan.details_parent)?;
+    }
+    integrity::write_manifest(root, &installed, &overlay_files, plan)?;
+    if let Some(key) = plan.signing_key {
+        signing::sign_manifest(root, &integrity::manifest_path(plan.details_parent), key)?;
+    }
+    Ok(BuildOutput {
+        files: installed,
+        patched_files,
+        overlay_files,
+    })
+}
+
+/// Copy the rule files extracted into `<from>/rules/` to `<to>/rules/` for every folder
+/// in `to`, adding the copies to `installed`.
+fn copy_rules(root: &Path, from: &str, to: &[&str], installed: &mut Vec<PathBuf>) -> Result<()> {
+    let source = Path::new(from).join("rules");
+    let rule_files: Vec<PathBuf> = installed
+        .iter()
+        .filter(|f| f.starts_with(&source))
+        .cloned()
+        .collect();
+    for rules_folder in to {
+        let dest = Path::new(rules_folder).join("rules");
+        for file in &rule_files {
+            let copy = dest.join(file.strip_prefix(&source)?);
+            if let Some(parent) = root.join(&copy).parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(root.join(file), root.join(&copy))
+                .with_context(|| format!("Failed to copy {}", file.display()))?;
+            installed.push(copy);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::io::Write;
+
+    fn create_test_zip(dir: &Path) -> PathBuf {
+        let zip_path = dir.join("test.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("aidlc-rules/aws-aidlc-rules/core-workflow.md", options)
+            .unwrap();
+        zip.write_all(b"Load `.kiro/aws-aidlc-rule-details/common/overview.md`\n")
+            .unwrap();
+        zip.start_file(
+            "aidlc-rules/aws-aidlc-rule-details/common/overview.md",
+            options,
+        )
+        .unwrap();
+        zip.write_all(b"# Overview").unwrap();
+        zip.finish().unwrap();
+        zip_path
+    }
+
+    /// An unsigned, unpatched install of release v0.1.1 without a commit workflow.
+    fn plan<'a>(agents: Vec<&'a dyn AgentAdapter>, details_parent: &'a str) -> InstallPlan<'a> {
+        InstallPlan {
+            agents,
+            details_parent,
+            commit_workflow: &CommitWorkflow::None,
+            release: "v0.1.1",
+            hash_mode: HashMode::Raw,
+            signing_key: None,
+            patches: &[],
+            overlay_dir: None,
+            overlay_index: false,
+        }
+    }
+
+    #[test]
+    fn builds_patched_install_with_signed_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_test_zip(dir.path());
+        let root = dir.path().join("staged");
+        let key = signing::generate_key().unwrap();
+        let plan = InstallPlan {
+            commit_workflow: &CommitWorkflow::Conventional,
+            signing_key: Some(&key),
+            ..plan(vec![&agent::AmazonQ], ".amazonq")
+        };
+
+        let output = build(&zip_path, &root, &plan).unwrap();
+        assert_eq!(output.files.len(), 2);
+        assert_eq!(
+            output.patched_files,
+            [PathBuf::from(".amazonq/rules/rules/core-workflow.md")]
+        );
+
+        let workflow =
+            fs::read_to_string(root.join(".amazonq/rules/rules/core-workflow.md")).unwrap();
+        assert!(workflow.contains(".amazonq/aws-aidlc-rule-details/common/overview.md"));
+        assert!(workflow.contains("Relative Paths Only"));
+        assert!(workflow.contains("Commit Workflow"));
+
+        for target in plan.targets() {
+            assert!(
+                root.join(&target).exists(),
+                "{} not built",
+                target.display()
+            );
+        }
+        let policy = signing::Policy {
+            required: true,
+            public_key: Some(key.verifying_key()),
+        };
+        assert!(
+            integrity::verify_manifest_with(&root, plan.details_parent, &policy)
+                .unwrap()
+                .unwrap()
+                .is_clean()
+        );
+    }
+
+    #[test]
+    fn kiro_front_matter_is_covered_by_the_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_test_zip(dir.path());
+        let root = dir.path().join("staged");
+        let kiro = agent::Kiro {
+            inclusion: agent::Inclusion::FileMatch,
+            file_match_pattern: Some("src/**/*.rs".to_string()),
+        };
+        build(&zip_path, &root, &plan(vec![&kiro], ".kiro")).unwrap();
+
+        let workflow =
+            fs::read_to_string(root.join(".kiro/steering/rules/core-workflow.md")).unwrap();
+        assert!(workflow.starts_with(
+            "---\ninclusion: fileMatch\nfileMatchPattern: \"src/**/*.rs\"\n---\n\nLoad `.kiro/"
+        ));
+
+        let manifest = integrity::read_manifest(&root, ".kiro").unwrap().unwrap();
+        // Rebuilt with the recorded settings, not the project's current ones.
+        let agents = manifest.agents(&Config::default()).unwrap().unwrap();
+        let recorded = InstallPlan::from_manifest(&manifest, ".kiro", &agents).unwrap();
+        assert_eq!(recorded.agents[0].rules_folder(), ".kiro/steering");
+        assert_eq!(
+            recorded.agents[0].front_matter(Path::new(".kiro/steering/rules/core-workflow.md")),
+            kiro.front_matter(Path::new(".kiro/steering/rules/core-workflow.md"))
+        );
+        assert_eq!(recorded.release, "v0.1.1");
+        assert_eq!(recorded.commit_workflow, &CommitWorkflow::None);
+        assert!(
+            integrity::verify_manifest(&root, ".kiro")
+                .unwrap()
+                .unwrap()
+                .is_clean()
+        );
+    }
+
+    #[test]
+    fn copilot_install_keeps_existing_instructions() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_test_zip(dir.path());
+        let root = dir.path().join("staged");
+        fs::create_dir_all(root.join(".github")).unwrap();
+        fs::write(
+            root.join(".github/copilot-instructions.md"),
+            "# Team instructions\n\nUse tabs.\n",
+        )
+        .unwrap();
+        let output = build(&zip_path, &root, &plan(vec![&agent::Copilot], ".github")).unwrap();
+
+        let workflow = Path::new(".github/instructions/rules/core-workflow.instructions.md");
+        assert!(output.files.iter().any(|f| f == workflow));
+        let content = fs::read_to_string(root.join(workflow)).unwrap();
+        assert!(
+            content
+                .starts_with("---\napplyTo: \"**\"\n---\n\nLoad `.github/aws-aidlc-rule-details/")
+        );
+        let instructions =
+            fs::read_to_string(root.join(".github/copilot-instructions.md")).unwrap();
+        assert!(
+            instructions.starts_with("# Team instructions\n\nUse tabs.\n\n<!-- aidlc-helper:begin")
+        );
+        assert!(
+            instructions.contains("`.github/instructions/rules/core-workflow.instructions.md`")
+        );
+        assert!(
+            integrity::verify_manifest(&root, ".github")
+                .unwrap()
+                .unwrap()
+                .is_clean()
+        );
+    }
+
+    #[test]
+    fn windsurf_rules_are_moved_out_of_the_rules_subfolder() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_test_zip(dir.path());
+        let root = dir.path().join("staged");
+        let plan = plan(vec![&agent::Windsurf], ".windsurf");
+
+        let output = build(&zip_path, &root, &plan).unwrap();
+
+        let workflow = PathBuf::from(".windsurf/rules/core-workflow.md");
+        assert!(output.files.contains(&workflow));
+        assert!(plan.targets().contains(&workflow));
+        assert!(!root.join(".windsurf/rules/rules").exists());
+        let content = fs::read_to_string(root.join(&workflow)).unwrap();
+        assert!(content.starts_with(
+            "---\ntrigger: always_on\n---\n\nLoad `.windsurf/aws-aidlc-rule-details/"
+        ));
+        assert!(
+            integrity::verify_manifest(&root, ".windsurf")
+                .unwrap()
+                .unwrap()
+                .is_clean()
+        );
+    }
+
+    #[test]
+    fn several_agents_share_one_details_tree_and_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = create_test_zip(dir.path());
+        let root = dir.path().join("staged");
+        let kiro = agent::Kiro::default();
+        let agents: Vec<&dyn AgentAdapter> =
+            vec![&kiro, &agent::Cursor, &agent::RootFile::AGENTS_MD];
+        let details_parent = agent::shared_details_parent(&agents);
+        assert_eq!(details_parent, ".aidlc");
+        let output = build(&zip_path, &root, &plan(agents, &details_parent)).unwrap();
+
+        assert_eq!(output.files.len(), 4);
+        assert!(
+            root.join(".aidlc/aws-aidlc-rule-details/common/overview.md")
+                .exists()
+        );
+        assert!(!root.join(".kiro/aws-aidlc-rule-details").exists());
+        let kiro_workflow =
+            fs::read_to_string(root.join(".kiro/steering/rules/core-workflow.md")).unwrap();
+        assert!(kiro_workflow.starts_with("---\ninclusion: always\n---\n\nLoad `.aidlc/"));
+        let cursor_workflow =
+            fs::read_to_string(root.join(".cursor/rules/rules/core-workflow.mdc")).unwrap();
+        assert!(cursor_workflow.contains("Load `.aidlc/aws-aidlc-rule-details/"));
+        let agents_md = fs::read_to_string(root.join("AGENTS.md")).unwrap();
+        assert!(agents_md.contains("`.aidlc/rules/core-workflow.md`"));
+
+        let manifest = integrity::read_manifest(&root, ".aidlc").unwrap().unwrap();
+        let ids: Vec<&str> = manifest.agents.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["kiro", "cursor", "agents-md"]);
+        assert_eq!(manifest.agent.as_deref(), Some("kiro"));
+        assert!(
+            integrity::verify_manifest(&root, ".aidlc")
+                .unwrap()
+                .unwrap()
+                .is_clean()
+        );
+    }
+}
@@ -1,68 +1,517 @@
 //! Integrity manifest for installed rule files.
 //!
-//! After installation, a `.aidlc-integrity.sha256` manifest is written containing
-//! SHA-256 hashes of all installed `.md` files. On subsequent runs, the manifest is
+//! After installation, a `.aidlc-integrity.sha256` manifest is written next to
+//! `aws-aidlc-rule-details/`. It is a versioned JSON document recording the release tag,
+//! the helper version, the hash algorithm, the install choices needed to rebuild a
+//! pristine copy, and a SHA-256 hash for every installed file, keyed by its path relative
+//! to the project root. On subsequent runs, the manifest is
 //! checked to detect if any rule files have been modified since installation — this
 //! warns users about potential tampering before they overwrite.
+//!
+//! Verification reports three kinds of drift: files whose content changed, files that
+//! were deleted, and files that appeared inside the installed directories without being
+//! part of the install.
+//!
+//! Text files can optionally be hashed in [`HashMode::Normalized`] mode, which also
+//! records a hash of the content with CRLF line endings and any UTF-8 BOM removed. A file
+//! whose raw bytes changed but whose normalized content didn't — typically a Windows
+//! checkout with `core.autocrlf=true` — is reported separately and not treated as
+//! modified.
+//!
+//! The manifest can also be signed with a team key (see [`crate::signing`]), so that a
+//! manifest regenerated by someone without the key no longer verifies.
+//!
+//! Manifests written by older versions (one `hash  path` line per file) are migrated to
+//! the current format in memory when read.
 
-use anyhow::{Context, Result};
+use crate::agent::{self, AgentAdapter};
+use crate::config::Config;
+use crate::install::InstallPlan;
+use crate::patchfile;
+use crate::prompt::CommitWorkflow;
+use crate::signing::{self, Policy, SignatureStatus};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 const MANIFEST_NAME: &str = ".aidlc-integrity.sha256";
+const MANIFEST_VERSION: u32 = 2;
+const ALGORITHM: &str = "sha256";
 
-/// Compute SHA-256 hashes of all installed .md files and write a manifest.
-pub fn write_manifest(installed_files: &[PathBuf], details_parent: &str) -> Result<()> {
-    let manifest_path = Path::new(details_parent).join(MANIFEST_NAME);
-    let mut lines = Vec::new();
+/// Parsed integrity manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub algorithm: String,
+    /// Release tag the files were installed from. `None` for migrated v1 manifests.
+    pub release: Option<String>,
+    /// Version of the helper that wrote the manifest. `None` for migrated v1 manifests.
+    pub tool_version: Option<String>,
+    /// Rules folder chosen at install time. `None` for manifests written before it was
+    /// recorded.
+    pub rules_folder: Option<String>,
+    /// Id of the [`AgentAdapter`] chosen at install time. `None` for manifests written
+    /// before it was recorded.
+    pub agent: Option<String>,
+    /// Settings of the first agent, see [`AgentAdapter::settings`]. `None` when it has
+    /// none, or for manifests written before they were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<serde_json::Value>,
+    /// Every agent the files were installed for, when the install covered more than one.
+    /// `rules_folder` and `agent` then describe the first of them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<AgentRecord>,
+    /// Commit workflow chosen at install time. `None` for manifests written before it was
+    /// recorded.
+    pub commit_workflow: Option<CommitWorkflow>,
+    /// Fingerprint of the user-defined patches applied at install time, see
+    /// [`patchfile::fingerprint`]. `None` when there were none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patches: Option<String>,
+    /// Directories fully owned by the install (e.g. `.kiro/aws-aidlc-rule-details`).
+    /// Any file inside them that isn't listed in `files` is reported as unexpected.
+    #[serde(default)]
+    pub tracked_dirs: Vec<String>,
+    /// One entry per installed file, sorted by path.
+    pub files: Vec<FileEntry>,
+}
 
-    for path in installed_files {
-        if path.extension().is_some_and(|e| e == "md") {
-            let bytes =
-                fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
-            let hash = format!("{:x}", Sha256::digest(&bytes));
-            lines.push(format!("{}  {}", hash, path.display()));
+/// One agent of a multi-agent install.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentRecord {
+    /// Id of the [`AgentAdapter`].
+    pub id: String,
+    /// Rules folder the agent's files were installed into.
+    pub rules_folder: String,
+    /// The agent's settings, see [`AgentAdapter::settings`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<serde_json::Value>,
+}
+
+/// Result of checking installed files against the manifest. Paths are relative to the
+/// project root.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Files whose content no longer matches the recorded hash.
+    pub modified: Vec<String>,
+    /// Files listed in the manifest that no longer exist.
+    pub missing: Vec<String>,
+    /// Files inside a tracked directory that aren't listed in the manifest.
+    pub unexpected: Vec<String>,
+    /// Normalized-mode files whose only changes are line endings or a BOM. Informational —
+    /// these don't count as modified.
+    pub line_endings: Vec<String>,
+    /// Outcome of checking the manifest signature.
+    pub signature: SignatureStatus,
+    /// Whether the project requires a valid signature.
+    pub signature_required: bool,
+}
+
+/// Overall outcome of a verification, most severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VerifyStatus {
+    /// No manifest was found, so nothing could be verified.
+    NoManifest,
+    /// A signature is required but missing or invalid, so the manifest can't be trusted.
+    BadSignature,
+    /// At least one installed file has been deleted.
+    Missing,
+    /// At least one installed file changed, or an unexpected file appeared.
+    Modified,
+    Clean,
+}
+
+impl VerifyReport {
+    /// True when every installed file is present and unchanged, with nothing extra, and
+    /// the manifest carries a valid signature if one is required.
+    pub fn is_clean(&self) -> bool {
+        self.modified.is_empty()
+            && self.missing.is_empty()
+            && self.unexpected.is_empty()
+            && !self.signature_failed()
+    }
+
+    /// True when a signature is required but missing or invalid.
+    pub fn signature_failed(&self) -> bool {
+        self.signature_required && self.signature != SignatureStatus::Valid
+    }
+
+    /// Overall outcome. A bad signature takes precedence over everything else, since
+    /// the file hashes themselves can't be trusted; then missing files, then modified or
+    /// unexpected ones.
+    pub fn status(&self) -> VerifyStatus {
+        if self.signature_failed() {
+            VerifyStatus::BadSignature
+        } else if !self.missing.is_empty() {
+            VerifyStatus::Missing
+        } else if !self.is_clean() {
+            VerifyStatus::Modified
+        } else {
+            VerifyStatus::Clean
+        }
+    }
+}
+
+impl Manifest {
+    /// The agents the files were installed for, set up with their recorded settings, or
+    /// `None` if the manifest predates recording the rules folder. Settings the manifest
+    /// predates are taken from the project `config`.
+    pub fn agents(&self, config: &Config) -> Result<Option<Vec<Box<dyn AgentAdapter>>>> {
+        let resolve =
+            |id: Option<&str>, rules_folder: &str, settings: Option<&serde_json::Value>| {
+                let mut agent = agent::resolve(id, rules_folder, config)?;
+                if let Some(settings) = settings {
+                    agent.load_settings(settings).with_context(|| {
+                        format!("Invalid {} settings in the integrity manifest", agent.id())
+                    })?;
+                }
+                Ok(agent)
+            };
+        if !self.agents.is_empty() {
+            return self
+                .agents
+                .iter()
+                .map(|a| resolve(Some(&a.id), &a.rules_folder, a.settings.as_ref()))
+                .collect::<Result<_>>()
+                .map(Some);
         }
+        let Some(rules_folder) = self.rules_folder.as_deref() else {
+            return Ok(None);
+        };
+        Ok(Some(vec![resolve(
+            self.agent.as_deref(),
+            rules_folder,
+            self.settings.as_ref(),
+        )?]))
+    }
+}
+
+/// How a file's hash is compared during verification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HashMode {
+    /// Any byte difference counts as a modification.
+    #[default]
+    Raw,
+    /// Differences in line endings (CRLF vs LF) or a UTF-8 BOM are tolerated.
+    Normalized,
+}
+
+/// Hash of a single installed file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// Path relative to the project root, `/`-separated on every platform.
+    pub path: String,
+    /// Hash of the raw file content.
+    pub hash: String,
+    #[serde(default)]
+    pub mode: HashMode,
+    /// Hash of the normalized content, recorded in [`HashMode::Normalized`] mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized_hash: Option<String>,
+    /// Where the file came from. Only recorded for overlay files.
+    #[serde(default, skip_serializing_if = "FileSource::is_upstream")]
+    pub source: FileSource,
+}
+
+/// Origin of an installed file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileSource {
+    /// Part of the upstream release.
+    #[default]
+    Upstream,
+    /// Copied from the team's overlay folder, see [`crate::overlay`].
+    Overlay,
+}
+
+impl FileSource {
+    fn is_upstream(&self) -> bool {
+        *self == FileSource::Upstream
     }
+}
+
+/// Path of the manifest, relative to the project root.
+pub fn manifest_path(details_parent: &str) -> PathBuf {
+    Path::new(details_parent).join(MANIFEST_NAME)
+}
 
-    lines.sort();
-    let mut content = lines.join("\n");
+/// Hash every installed file and write the manifest for `plan`. Files also listed in
+/// `overlay_files` are recorded as coming from the overlay.
+///
+/// `installed_files` and the plan's folders are relative to `root` — the project root or
+/// a staging directory mirroring it. In normalized mode, files that aren't valid UTF-8
+/// are still hashed raw.
+pub fn write_manifest(
+    root: &Path,
+    installed_files: &[PathBuf],
+    overlay_files: &[PathBuf],
+    plan: &InstallPlan,
+) -> Result<()> {
+    let mut files = Vec::new();
+    for path in installed_files {
+        let bytes = fs::read(root.join(path))
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let normalized =
+            plan.hash_mode == HashMode::Normalized && std::str::from_utf8(&bytes).is_ok();
+        files.push(FileEntry {
+            path: to_manifest_path(path)?,
+            hash: hash_bytes(&bytes),
+            mode: if normalized {
+                HashMode::Normalized
+            } else {
+                HashMode::Raw
+            },
+            normalized_hash: normalized.then(|| hash_bytes(&normalize(&bytes))),
+            source: if overlay_files.contains(path) {
+                FileSource::Overlay
+            } else {
+                FileSource::Upstream
+            },
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let Some(first) = plan.agents.first() else {
+        bail!("No agent to record in the manifest");
+    };
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        algorithm: ALGORITHM.to_string(),
+        release: Some(plan.release.to_string()),
+        tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        rules_folder: Some(first.rules_folder().to_string()),
+        agent: Some(first.id().to_string()),
+        settings: first.settings(),
+        agents: if plan.agents.len() > 1 {
+            plan.agents
+                .iter()
+                .map(|a| AgentRecord {
+                    id: a.id().to_string(),
+                    rules_folder: a.rules_folder().to_string(),
+                    settings: a.settings(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        },
+        commit_workflow: Some(*plan.commit_workflow),
+        patches: patchfile::fingerprint(plan.patches),
+        tracked_dirs: plan
+            .tracked_dirs()
+            .iter()
+            .map(|d| to_manifest_path(d))
+            .collect::<Result<_>>()?,
+        files,
+    };
+    let mut content = serde_json::to_string_pretty(&manifest)?;
     content.push('\n');
-    fs::write(&manifest_path, content)?;
+    fs::write(root.join(manifest_path(plan.details_parent)), content)?;
     Ok(())
 }
 
-/// Verify existing files against the manifest. Returns list of modified file paths.
-pub fn verify_manifest(details_parent: &str) -> Result<Vec<String>> {
-    let manifest_path = Path::new(details_parent).join(MANIFEST_NAME);
-    if !manifest_path.exists() {
-        return Ok(Vec::new());
+/// Read the manifest under `root`, migrating older formats. Returns `None` if there is none.
+pub fn read_manifest(root: &Path, details_parent: &str) -> Result<Option<Manifest>> {
+    let path = root.join(manifest_path(details_parent));
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)?;
+    let manifest = if content.trim_start().starts_with('{') {
+        serde_json::from_str(&content)
+            .with_context(|| format!("Corrupted integrity manifest: {}", path.display()))?
+    } else {
+        migrate_v1(&content)
+    };
+    if manifest.version > MANIFEST_VERSION {
+        bail!(
+            "{} was written by a newer aidlc-workflows-helper (manifest v{}) — please upgrade",
+            path.display(),
+            manifest.version
+        );
+    }
+    if manifest.algorithm != ALGORITHM {
+        bail!(
+            "Unsupported hash algorithm {:?} in {}",
+            manifest.algorithm,
+            path.display()
+        );
+    }
+    // Entries are joined onto the project root, so one pointing elsewhere would make
+    // `verify` and `diff` read files outside the project.
+    let entries = manifest.files.iter().map(|f| &f.path);
+    if let Some(entry) = entries
+        .chain(&manifest.tracked_dirs)
+        .find(|entry| !is_manifest_path(entry))
+    {
+        bail!(
+            "Corrupted integrity manifest: {} lists {entry:?}, which isn't a path inside the project",
+            path.display()
+        );
+    }
+    Ok(Some(manifest))
+}
+
+/// Verify installed files against the manifest, ignoring signatures. Returns `None` if
+/// there is no manifest.
+pub fn verify_manifest(root: &Path, details_parent: &str) -> Result<Option<VerifyReport>> {
+    verify_manifest_with(root, details_parent, &Policy::default())
+}
+
+/// Verify installed files against the manifest, and its signature according to `policy`.
+/// Returns `None` if there is no manifest.
+pub fn verify_manifest_with(
+    root: &Path,
+    details_parent: &str,
+    policy: &Policy,
+) -> Result<Option<VerifyReport>> {
+    let Some(manifest) = read_manifest(root, details_parent)? else {
+        return Ok(None);
+    };
+
+    let path = manifest_path(details_parent);
+    let signature_path = root.join(signing::signature_path(&path));
+    let signature = if signature_path.exists() {
+        Some(fs::read_to_string(&signature_path)?)
+    } else {
+        None
+    };
+    let mut report = VerifyReport {
+        signature: policy.check(&fs::read(root.join(&path))?, signature.as_deref()),
+        signature_required: policy.required,
+        ..VerifyReport::default()
+    };
+    for entry in &manifest.files {
+        let file_path = root.join(&entry.path);
+        if !file_path.is_file() {
+            report.missing.push(entry.path.clone());
+            continue;
+        }
+        let bytes = fs::read(&file_path)?;
+        if hash_bytes(&bytes) == entry.hash {
+            continue;
+        }
+        match &entry.normalized_hash {
+            Some(expected) if *expected == hash_bytes(&normalize(&bytes)) => {
+                report.line_endings.push(entry.path.clone());
+            }
+            _ => report.modified.push(entry.path.clone()),
+        }
+    }
+
+    let known: BTreeSet<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    let mut present = BTreeSet::new();
+    for dir in &manifest.tracked_dirs {
+        collect_files(root, Path::new(dir), &mut present)?;
+    }
+    report.unexpected = present
+        .into_iter()
+        .filter(|path| !known.contains(path.as_str()))
+        .collect();
+
+    Ok(Some(report))
+}
+
+/// Recursively collect the files under `root/dir` as manifest paths.
+fn collect_files(root: &Path, dir: &Path, out: &mut BTreeSet<String>) -> Result<()> {
+    let full = root.join(dir);
+    if !full.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(&full)? {
+        let entry = entry?;
+        let rel = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &rel, out)?;
+        } else {
+            out.insert(to_manifest_path(&rel)?);
+        }
     }
+    Ok(())
+}
 
-    let content = fs::read_to_string(&manifest_path)?;
-    let expected: BTreeMap<String, String> = content
+/// Convert a v1 manifest (`<hash>  <path>` per line) to the current format.
+///
+/// v1 paths were recorded relative to the working directory of the install, which was
+/// always the project root, so they are kept as-is apart from a leading `./`. v1 didn't
+/// record tracked directories; the `aws-aidlc-rule-details` tree and the folder holding
+/// each rules file are assumed.
+fn migrate_v1(content: &str) -> Manifest {
+    let files: Vec<FileEntry> = content
         .lines()
         .filter_map(|line| {
             let (hash, path) = line.split_once("  ")?;
-            Some((path.to_string(), hash.to_string()))
+            Some(FileEntry {
+                path: path.trim_start_matches("./").replace('\\', "/"),
+                hash: hash.to_string(),
+                mode: HashMode::Raw,
+                normalized_hash: None,
+                source: FileSource::Upstream,
+            })
         })
         .collect();
+    let tracked_dirs: BTreeSet<String> = files
+        .iter()
+        .filter_map(|f| match f.path.find("aws-aidlc-rule-details/") {
+            Some(i) => Some(f.path[..i + "aws-aidlc-rule-details".len()].to_string()),
+            None => f.path.rsplit_once('/').map(|(dir, _)| dir.to_string()),
+        })
+        .collect();
+    Manifest {
+        version: 1,
+        algorithm: ALGORITHM.to_string(),
+        release: None,
+        tool_version: None,
+        rules_folder: None,
+        agent: None,
+        settings: None,
+        agents: Vec::new(),
+        commit_workflow: None,
+        patches: None,
+        tracked_dirs: tracked_dirs.into_iter().collect(),
+        files,
+    }
+}
 
-    let mut modified = Vec::new();
-    for (path, expected_hash) in &expected {
-        let file_path = Path::new(path);
-        if file_path.exists() {
-            let bytes = fs::read(file_path)?;
-            let actual = format!("{:x}", Sha256::digest(&bytes));
-            if actual != *expected_hash {
-                modified.push(path.clone());
-            }
+/// Render a relative path with `/` separators, rejecting anything that isn't a plain
+/// relative path (absolute, or containing `..`).
+fn to_manifest_path(path: &Path) -> Result<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            _ => bail!("Not a project-relative path: {}", path.display()),
         }
     }
+    Ok(parts.join("/"))
+}
 
-    Ok(modified)
+/// Whether `path` is in the form [`to_manifest_path`] writes: a non-empty relative path
+/// with `/` separators and no `.` or `..` components.
+fn is_manifest_path(path: &str) -> bool {
+    !path.is_empty() && to_manifest_path(Path::new(path)).is_ok_and(|p| p == path)
+}
+
+/// Strip a leading UTF-8 BOM and convert CRLF line endings to LF.
+fn normalize(bytes: &[u8]) -> Vec<u8> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let mut out = Vec::with_capacity(bytes.len());
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'\r' && bytes.get(i + 1) == Some(&b'\n') {
+            continue;
+        }
+        out.push(b);
+    }
+    out
+}
+
+/// Hex-encoded SHA-256 of `bytes`, as recorded in the manifest.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
 }
 
 #[cfg(test)]
@@ -70,60 +519,356 @@ mod tests {
     use super::*;
     use std::fs;
 
+    static KIRO: agent::Kiro = agent::Kiro {
+        inclusion: agent::Inclusion::Always,
+        file_match_pattern: None,
+    };
+
+    fn plan(details_parent: &str) -> InstallPlan<'_> {
+        InstallPlan {
+            agents: vec![&KIRO],
+            details_parent,
+            commit_workflow: &CommitWorkflow::None,
+            release: "v1.0.0",
+            hash_mode: HashMode::Raw,
+            signing_key: None,
+            patches: &[],
+            overlay_dir: None,
+            overlay_index: false,
+        }
+    }
+
     #[test]
     fn write_and_verify_unmodified() {
         let dir = tempfile::tempdir().unwrap();
-        let parent = dir.path().to_string_lossy().to_string();
+        let root = dir.path();
 
-        let md_file = dir.path().join("test.md");
-        fs::write(&md_file, "# Hello").unwrap();
+        fs::write(root.join("test.md"), "# Hello").unwrap();
 
-        write_manifest(std::slice::from_ref(&md_file), &parent).unwrap();
+        write_manifest(root, &[PathBuf::from("test.md")], &[], &plan(".")).unwrap();
 
         let manifest = dir.path().join(".aidlc-integrity.sha256");
         assert!(manifest.exists());
 
-        let modified = verify_manifest(&parent).unwrap();
-        assert!(modified.is_empty());
+        assert!(verify_manifest(root, ".").unwrap().unwrap().is_clean());
+    }
+
+    #[test]
+    fn marks_overlay_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("upstream.md"), "# Upstream").unwrap();
+        fs::write(root.join("team.md"), "# Team").unwrap();
+        let files = [PathBuf::from("team.md"), PathBuf::from("upstream.md")];
+
+        write_manifest(root, &files, &files[..1], &plan(".")).unwrap();
+
+        let manifest = read_manifest(root, ".").unwrap().unwrap();
+        let sources: Vec<_> = manifest.files.iter().map(|f| f.source).collect();
+        assert_eq!(sources, [FileSource::Overlay, FileSource::Upstream]);
+        let json = fs::read_to_string(root.join(".aidlc-integrity.sha256")).unwrap();
+        assert_eq!(json.matches("\"source\"").count(), 1);
     }
 
     #[test]
     fn detects_modified_file() {
         let dir = tempfile::tempdir().unwrap();
-        let parent = dir.path().to_string_lossy().to_string();
+        let root = dir.path();
 
-        let md_file = dir.path().join("test.md");
-        fs::write(&md_file, "# Original").unwrap();
+        fs::write(root.join("test.md"), "# Original").unwrap();
 
-        write_manifest(std::slice::from_ref(&md_file), &parent).unwrap();
+        write_manifest(root, &[PathBuf::from("test.md")], &[], &plan(".")).unwrap();
 
         // Tamper with the file
-        fs::write(&md_file, "# Tampered").unwrap();
+        fs::write(root.join("test.md"), "# Tampered").unwrap();
+
+        let report = verify_manifest(root, ".").unwrap().unwrap();
+        assert_eq!(report.modified, vec!["test.md"]);
+        assert!(report.missing.is_empty());
+    }
+
+    #[test]
+    fn records_metadata_and_covers_all_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join(".kiro/aws-aidlc-rule-details")).unwrap();
+        fs::write(
+            root.join(".kiro/aws-aidlc-rule-details/readme.txt"),
+            "hello",
+        )
+        .unwrap();
+        fs::write(root.join(".kiro/aws-aidlc-rule-details/a.md"), "# A").unwrap();
+
+        let installed = [
+            PathBuf::from(".kiro/aws-aidlc-rule-details/readme.txt"),
+            PathBuf::from("./.kiro/aws-aidlc-rule-details/a.md"),
+        ];
+        write_manifest(root, &installed, &[], &plan(".kiro")).unwrap();
+
+        let manifest = read_manifest(root, ".kiro").unwrap().unwrap();
+        assert_eq!(manifest.version, MANIFEST_VERSION);
+        assert_eq!(manifest.algorithm, "sha256");
+        assert_eq!(manifest.release.as_deref(), Some("v1.0.0"));
+        assert_eq!(manifest.rules_folder.as_deref(), Some(".kiro/steering"));
+        assert_eq!(manifest.commit_workflow, Some(CommitWorkflow::None));
+        assert_eq!(manifest.patches, None);
+        assert_eq!(manifest.agent.as_deref(), Some("kiro"));
+        assert!(manifest.agents.is_empty());
+        let agents = manifest.agents(&Config::default()).unwrap().unwrap();
+        assert_eq!(agents[0].rules_folder(), ".kiro/steering");
+        assert_eq!(
+            manifest.tool_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(
+            manifest.tracked_dirs,
+            [".kiro/steering/rules", ".kiro/aws-aidlc-rule-details"]
+        );
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                ".kiro/aws-aidlc-rule-details/a.md",
+                ".kiro/aws-aidlc-rule-details/readme.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_are_relative_to_root_not_working_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir_all(root.join("rules")).unwrap();
+        fs::write(root.join("rules/core-workflow.md"), "# Core").unwrap();
+
+        let installed = [PathBuf::from("rules/core-workflow.md")];
+        write_manifest(&root, &installed, &[], &plan(".")).unwrap();
+        let manifest = read_manifest(&root, ".").unwrap().unwrap();
+        assert_eq!(manifest.files[0].path, "rules/core-workflow.md");
+
+        fs::write(root.join("rules/core-workflow.md"), "# Changed").unwrap();
+        assert_eq!(
+            verify_manifest(&root, ".").unwrap().unwrap().modified,
+            vec!["rules/core-workflow.md"]
+        );
+    }
+
+    #[test]
+    fn records_the_applied_patches() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.md"), "# A").unwrap();
+        let patches = [patchfile::Operation::RemoveSection {
+            file: "rules/core-workflow.md".to_string(),
+            heading: "Operations Phase".to_string(),
+        }];
+        let plan = InstallPlan {
+            patches: &patches,
+            ..plan(".")
+        };
+
+        write_manifest(root, &[PathBuf::from("a.md")], &[], &plan).unwrap();
+        let manifest = read_manifest(root, ".").unwrap().unwrap();
+        assert!(manifest.patches.is_some());
+        assert_eq!(manifest.patches, patchfile::fingerprint(&patches));
+    }
+
+    #[test]
+    fn handles_paths_with_double_spaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("my  notes.md"), "# Notes").unwrap();
+
+        write_manifest(root, &[PathBuf::from("my  notes.md")], &[], &plan(".")).unwrap();
+        let manifest = read_manifest(root, ".").unwrap().unwrap();
+        assert_eq!(manifest.files[0].path, "my  notes.md");
+        assert!(verify_manifest(root, ".").unwrap().unwrap().is_clean());
+    }
+
+    #[test]
+    fn migrates_v1_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("test.md"), "# Hello").unwrap();
+        let hash = hash_bytes(b"# Hello");
+        fs::write(
+            root.join(MANIFEST_NAME),
+            format!("{hash}  ./test.md\n{hash}  .kiro/aws-aidlc-rule-details/common/x.md\n"),
+        )
+        .unwrap();
 
-        let modified = verify_manifest(&parent).unwrap();
-        assert_eq!(modified.len(), 1);
+        let manifest = read_manifest(root, ".").unwrap().unwrap();
+        assert_eq!(manifest.version, 1);
+        assert_eq!(manifest.release, None);
+        assert!(manifest.agents(&Config::default()).unwrap().is_none());
+        assert_eq!(manifest.files[0].path, "test.md");
+        assert_eq!(manifest.tracked_dirs, [".kiro/aws-aidlc-rule-details"]);
+
+        let report = verify_manifest(root, ".").unwrap().unwrap();
+        assert!(report.modified.is_empty());
+        assert_eq!(report.missing, [".kiro/aws-aidlc-rule-details/common/x.md"]);
+    }
+
+    #[test]
+    fn detects_missing_and_unexpected_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let details = root.join(".kiro/aws-aidlc-rule-details");
+        fs::create_dir_all(details.join("inception")).unwrap();
+        fs::write(details.join("inception/stage.md"), "# Stage").unwrap();
+        fs::write(details.join("inception/other.md"), "# Other").unwrap();
+
+        let installed = [
+            PathBuf::from(".kiro/aws-aidlc-rule-details/inception/stage.md"),
+            PathBuf::from(".kiro/aws-aidlc-rule-details/inception/other.md"),
+        ];
+        write_manifest(root, &installed, &[], &plan(".kiro")).unwrap();
+
+        fs::remove_file(details.join("inception/stage.md")).unwrap();
+        fs::create_dir_all(details.join("common")).unwrap();
+        fs::write(details.join("common/injected.md"), "# Injected").unwrap();
+
+        let report = verify_manifest(root, ".kiro").unwrap().unwrap();
+        assert!(report.modified.is_empty());
+        assert_eq!(
+            report.missing,
+            [".kiro/aws-aidlc-rule-details/inception/stage.md"]
+        );
+        assert_eq!(
+            report.unexpected,
+            [".kiro/aws-aidlc-rule-details/common/injected.md"]
+        );
+        assert!(!report.is_clean());
+        assert_eq!(report.status(), VerifyStatus::Missing);
+    }
+
+    #[test]
+    fn status_reflects_most_severe_problem() {
+        let mut report = VerifyReport::default();
+        assert_eq!(report.status(), VerifyStatus::Clean);
+        report.unexpected.push("extra.md".to_string());
+        assert_eq!(report.status(), VerifyStatus::Modified);
+        report.missing.push("gone.md".to_string());
+        assert_eq!(report.status(), VerifyStatus::Missing);
+        report.signature = SignatureStatus::Invalid;
+        assert_eq!(report.status(), VerifyStatus::Missing);
+        report.signature_required = true;
+        assert_eq!(report.status(), VerifyStatus::BadSignature);
+    }
+
+    #[test]
+    fn regenerated_manifest_fails_signature_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let key = signing::generate_key().unwrap();
+        fs::write(root.join("test.md"), "# Hello").unwrap();
+        write_manifest(root, &[PathBuf::from("test.md")], &[], &plan(".")).unwrap();
+        signing::sign_manifest(root, &manifest_path("."), &key).unwrap();
+        let policy = Policy {
+            required: true,
+            public_key: Some(key.verifying_key()),
+        };
+        let report = verify_manifest_with(root, ".", &policy).unwrap().unwrap();
+        assert_eq!(report.signature, SignatureStatus::Valid);
+        assert!(report.is_clean());
+
+        // Tamper with a file and regenerate the manifest without the key.
+        fs::write(root.join("test.md"), "# Tampered").unwrap();
+        write_manifest(root, &[PathBuf::from("test.md")], &[], &plan(".")).unwrap();
+
+        let report = verify_manifest_with(root, ".", &policy).unwrap().unwrap();
+        assert!(report.modified.is_empty());
+        assert_eq!(report.signature, SignatureStatus::Invalid);
+        assert_eq!(report.status(), VerifyStatus::BadSignature);
+
+        // Without a key configured, the signature isn't checked.
+        let report = verify_manifest(root, ".").unwrap().unwrap();
+        assert_eq!(report.signature, SignatureStatus::Unchecked);
+        assert!(report.is_clean());
     }
 
     #[test]
-    fn skips_non_md_files() {
+    fn rejects_manifest_entries_outside_the_project() {
         let dir = tempfile::tempdir().unwrap();
-        let parent = dir.path().to_string_lossy().to_string();
+        let root = dir.path().join("project");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("test.md"), "# Hello").unwrap();
+        fs::write(dir.path().join("secret.md"), "# Secret").unwrap();
+        write_manifest(&root, &[PathBuf::from("test.md")], &[], &plan(".")).unwrap();
+        let manifest = read_manifest(&root, ".").unwrap().unwrap();
 
-        let txt_file = dir.path().join("readme.txt");
-        fs::write(&txt_file, "hello").unwrap();
+        for path in ["../secret.md", "/etc/passwd", "./test.md", ""] {
+            let mut tampered = manifest.clone();
+            tampered.files[0].path = path.to_string();
+            fs::write(
+                root.join(MANIFEST_NAME),
+                serde_json::to_string(&tampered).unwrap(),
+            )
+            .unwrap();
+            assert!(read_manifest(&root, ".").is_err(), "{path:?} was accepted");
+            assert!(verify_manifest(&root, ".").is_err());
+        }
+        let mut tampered = manifest;
+        tampered.tracked_dirs = vec!["..".to_string()];
+        fs::write(
+            root.join(MANIFEST_NAME),
+            serde_json::to_string(&tampered).unwrap(),
+        )
+        .unwrap();
+        assert!(verify_manifest(&root, ".").is_err());
+    }
+
+    #[test]
+    fn normalizes_line_endings_and_bom() {
+        assert_eq!(normalize(b"\xEF\xBB\xBFa\r\nb\r\n"), b"a\nb\n");
+        assert_eq!(normalize(b"a\rb\n"), b"a\rb\n");
+    }
+
+    #[test]
+    fn normalized_mode_reports_line_ending_changes_separately() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("eol.md"), "# Title\nBody\n").unwrap();
+        fs::write(root.join("edited.md"), "# Title\nBody\n").unwrap();
+
+        let installed = [PathBuf::from("eol.md"), PathBuf::from("edited.md")];
+        let plan = InstallPlan {
+            hash_mode: HashMode::Normalized,
+            ..plan(".")
+        };
+        write_manifest(root, &installed, &[], &plan).unwrap();
 
-        write_manifest(&[txt_file], &parent).unwrap();
+        fs::write(root.join("eol.md"), "\u{FEFF}# Title\r\nBody\r\n").unwrap();
+        fs::write(root.join("edited.md"), "# Title\r\nChanged\r\n").unwrap();
 
-        let manifest = fs::read_to_string(dir.path().join(".aidlc-integrity.sha256")).unwrap();
-        // Only a trailing newline — no entries for .txt files
-        assert_eq!(manifest.trim(), "");
+        let report = verify_manifest(root, ".").unwrap().unwrap();
+        assert_eq!(report.line_endings, ["eol.md"]);
+        assert_eq!(report.modified, ["edited.md"]);
+    }
+
+    #[test]
+    fn raw_mode_treats_line_ending_changes_as_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("eol.md"), "# Title\n").unwrap();
+        write_manifest(root, &[PathBuf::from("eol.md")], &[], &plan(".")).unwrap();
+
+        fs::write(root.join("eol.md"), "# Title\r\n").unwrap();
+
+        let report = verify_manifest(root, ".").unwrap().unwrap();
+        assert_eq!(report.modified, ["eol.md"]);
+        assert!(report.line_endings.is_empty());
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        assert!(to_manifest_path(Path::new("/etc/passwd")).is_err());
+        assert!(to_manifest_path(Path::new("../outside.md")).is_err());
     }
 
     #[test]
     fn no_manifest_returns_empty() {
         let dir = tempfile::tempdir().unwrap();
-        let parent = dir.path().to_string_lossy().to_string();
-        let modified = verify_manifest(&parent).unwrap();
-        assert!(modified.is_empty());
+        assert!(verify_manifest(dir.path(), ".").unwrap().is_none());
     }
 }
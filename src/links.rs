@@ -0,0 +1,198 @@
+//! Checks that the paths installed rule files point at actually exist.
+//!
+//! Rule files tell the agent which other files to load — through markdown links,
+//! backticked paths and bare `<folder>/aws-aidlc-rule-details/...` references. After
+//! patching, every one of them should resolve; a dangling one means the agent will be told
+//! to load a file that isn't there.
+//!
+//! A reference resolves if it exists relative to the file containing it, the project
+//! root, the details parent, or the `aws-aidlc-rule-details/` tree itself — upstream uses
+//! all four styles. URLs, anchors, absolute paths, `aidlc-docs/` (created by the workflow
+//! at runtime) and placeholders such as `<unit-name>` or `{stage}` aren't checked, and
+//! neither is anything inside a fenced code block.
+
+use crate::{agent, patch};
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+static LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\[[^\]]*\]\(<?([^)\s>]+)>?(?:\s+"[^"]*")?\)"#).expect("valid link pattern")
+});
+static CODE_SPAN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"`([^`\s]+)`").expect("valid code span pattern"));
+
+/// A reference that doesn't resolve to an existing file or directory.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// File containing the reference, relative to the project root.
+    pub file: String,
+    /// 1-based line of the first occurrence in `file`.
+    pub line: usize,
+    /// The reference as written.
+    pub target: String,
+}
+
+/// Check every markdown file in `files` (relative to `root`) for references that don't
+/// resolve. Each broken target is reported once per file, at its first occurrence.
+pub fn check(root: &Path, files: &[PathBuf], details_parent: &str) -> Result<Vec<BrokenLink>> {
+    let bases = [
+        root.to_path_buf(),
+        root.join(details_parent),
+        root.join(details_parent).join("aws-aidlc-rule-details"),
+    ];
+    let mut broken = Vec::new();
+    for file in files {
+        if !agent::is_markdown(file) {
+            continue;
+        }
+        let content = fs::read_to_string(root.join(file))
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let file_dir = root.join(file).parent().map(Path::to_path_buf);
+
+        let mut seen = BTreeSet::new();
+        let mut in_fence = false;
+        for (index, line) in content.lines().enumerate() {
+            if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+                in_fence = !in_fence;
+                continue;
+            }
+            if in_fence {
+                continue;
+            }
+            for target in references(line) {
+                if !is_checkable(target) || seen.contains(target) {
+                    continue;
+                }
+                seen.insert(target.to_string());
+                let resolves = file_dir
+                    .iter()
+                    .chain(&bases)
+                    .any(|base| base.join(target).exists());
+                if !resolves {
+                    broken.push(BrokenLink {
+                        file: file.to_string_lossy().replace('\\', "/"),
+                        line: index + 1,
+                        target: target.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(broken)
+}
+
+/// Candidate paths on one line: link targets (without their `#anchor`), backticked
+/// paths, and bare rule-details references.
+fn references(line: &str) -> Vec<&str> {
+    let links = LINK
+        .captures_iter(line)
+        .map(|caps| caps.get(1).expect("group 1 always matches").as_str())
+        .map(|target| target.split('#').next().unwrap_or_default());
+    let code_spans = CODE_SPAN
+        .captures_iter(line)
+        .map(|caps| caps.get(1).expect("group 1 always matches").as_str())
+        .filter(|span| span.contains('/') || span.ends_with(".md"));
+    links
+        .chain(code_spans)
+        .chain(patch::rule_details_references(line))
+        .collect()
+}
+
+/// False for references that aren't local paths, or that only exist once the workflow
+/// runs.
+fn is_checkable(target: &str) -> bool {
+    !target.is_empty()
+        && !target.contains("://")
+        && !target.starts_with("mailto:")
+        && !target.starts_with('/')
+        && !target.starts_with('~')
+        && !target.starts_with("aidlc-docs/")
+        && !target.contains("...")
+        && !target.contains(['<', '>', '{', '}', '[', ']', '*', '$'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(root: &Path) -> Vec<PathBuf> {
+        let details = root.join(".kiro/aws-aidlc-rule-details");
+        fs::create_dir_all(details.join("common")).unwrap();
+        fs::create_dir_all(root.join(".kiro/steering/rules")).unwrap();
+        fs::write(details.join("common/a.md"), "See [b](b.md#intro)\n").unwrap();
+        fs::write(details.join("common/b.md"), "# B\n").unwrap();
+        vec![
+            PathBuf::from(".kiro/steering/rules/core-workflow.md"),
+            PathBuf::from(".kiro/aws-aidlc-rule-details/common/a.md"),
+            PathBuf::from(".kiro/aws-aidlc-rule-details/common/b.md"),
+        ]
+    }
+
+    #[test]
+    fn resolves_every_reference_style() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = setup(dir.path());
+        fs::write(
+            dir.path().join(&files[0]),
+            "Load `.kiro/aws-aidlc-rule-details/common/a.md` and `common/b.md`.\n\
+             Then .kiro/aws-aidlc-rule-details/common/b.md and \
+             [details](../../aws-aidlc-rule-details/common/).\n",
+        )
+        .unwrap();
+
+        assert!(check(dir.path(), &files, ".kiro").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_dangling_references_once_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = setup(dir.path());
+        fs::write(
+            dir.path().join(&files[0]),
+            "# Workflow\n\
+             Load `.kiro/aws-aidlc-rule-details/common/gone.md`.\n\
+             See .kiro/aws-aidlc-rule-details/common/gone.md and [x](missing.md).\n",
+        )
+        .unwrap();
+
+        let broken = check(dir.path(), &files, ".kiro").unwrap();
+        assert_eq!(
+            broken,
+            [
+                BrokenLink {
+                    file: ".kiro/steering/rules/core-workflow.md".to_string(),
+                    line: 2,
+                    target: ".kiro/aws-aidlc-rule-details/common/gone.md".to_string(),
+                },
+                BrokenLink {
+                    file: ".kiro/steering/rules/core-workflow.md".to_string(),
+                    line: 3,
+                    target: "missing.md".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_urls_placeholders_runtime_paths_and_code_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = setup(dir.path());
+        fs::write(
+            dir.path().join(&files[0]),
+            "[site](https://example.com/x.md) [top](#top) `/Users/me/x.md`\n\
+             `aidlc-docs/inception/plan.md` `construction/{unit-name}/code.md`\n\
+             `construction/<unit>/design.md` `feat:`\n\
+             ```\n\
+             cat not/a/file.md\n\
+             `inside/fence.md`\n\
+             ```\n",
+        )
+        .unwrap();
+
+        assert!(check(dir.path(), &files, ".kiro").unwrap().is_empty());
+    }
+}
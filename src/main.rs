@@ -1,65 +1,175 @@
 //! aidlc-workflows-helper — CLI tool to install AI-DLC workflow rules into any project.
 //!
 //! This binary orchestrates the full interactive installation flow:
-//! folder selection → download → extract → patch → commit workflow → gitignore → integrity manifest.
+//! folder selection → commit workflow → download → staged install (extract → patch →
+//! integrity manifest → swap into place) → gitignore.
+//!
+//! Subcommands (see [`cli::Command`]) handle maintenance of an existing installation.
 
+mod agent;
+mod backup;
 mod banner;
 mod cache;
+mod cli;
+mod config;
+mod diff;
 mod download;
 mod extract;
 mod github;
 mod gitignore;
+mod install;
 mod integrity;
+mod links;
+mod markdown;
+mod overlay;
 mod patch;
+mod patchfile;
 mod prompt;
+mod signing;
+mod staging;
 mod ui;
 
-use anyhow::Result;
+use agent::AgentAdapter;
+use anyhow::{Context, Result, bail};
+use clap::Parser;
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::time::Duration;
 
-fn main() {
-    if let Err(e) = run() {
-        ui::error(&format!("{e:#}"));
-        std::process::exit(1);
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            ui::error(&format!("{e:#}"));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Dispatches to the requested subcommand, or the installation flow when none is given.
+/// Returns an error if any step fails, which `main()` catches and displays as a styled
+/// error message before exiting non-zero.
+fn run() -> Result<ExitCode> {
+    let cli = cli::Cli::parse();
+    // All paths (prompts, manifest, .gitignore) are relative to the project root, which
+    // is the working directory unless `--project-root` names another one.
+    if let Some(project_root) = &cli.project_root {
+        std::env::set_current_dir(project_root)
+            .with_context(|| format!("Failed to enter project root {}", project_root.display()))?;
+    }
+    for target in staging::recover(Path::new("."))? {
+        ui::warn(&format!(
+            "Restored {} from an install that was interrupted",
+            target.display()
+        ));
+    }
+    match cli.command {
+        None => run_install(&cli.agents).map(|()| ExitCode::SUCCESS),
+        Some(cli::Command::Restore { list, id }) => {
+            run_restore(list, id).map(|()| ExitCode::SUCCESS)
+        }
+        Some(cli::Command::Verify {
+            details_parent,
+            github_annotations,
+        }) => run_verify(details_parent, github_annotations),
+        Some(cli::Command::Diff { details_parent }) => {
+            run_diff(details_parent).map(|()| ExitCode::SUCCESS)
+        }
+        Some(cli::Command::CheckLinks { details_parent }) => run_check_links(details_parent),
+        Some(cli::Command::Uninstall { details_parent }) => {
+            run_uninstall(details_parent).map(|()| ExitCode::SUCCESS)
+        }
+        Some(cli::Command::Keygen { force }) => run_keygen(force).map(|()| ExitCode::SUCCESS),
+        Some(cli::Command::Sign { details_parent }) => {
+            run_sign(details_parent).map(|()| ExitCode::SUCCESS)
+        }
     }
 }
 
-/// Runs the full interactive installation flow. Returns an error if any step fails,
-/// which `main()` catches and displays as a styled error message before exiting non-zero.
-fn run() -> Result<()> {
+/// Runs the full interactive installation flow. `agent_args` are the `--agent` values;
+/// the agents are prompted for when there are none.
+fn run_install(agent_args: &[String]) -> Result<()> {
     banner::print_banner();
 
     const TOTAL: u8 = 6;
+    let project_root = Path::new(".");
+    let config = config::load(project_root)?;
+    let policy = signing::Policy::load(project_root, &config.integrity)?;
+    let signing_key = match signing::find_signing_key()? {
+        Some(key) if policy.required => {
+            policy.check_key(&key)?;
+            Some(key)
+        }
+        Some(key) => match policy.check_key(&key) {
+            Ok(()) => Some(key),
+            Err(e) => {
+                ui::warn(&format!("{e} — the manifest won't be signed"));
+                None
+            }
+        },
+        None => None,
+    };
+    let patches = patchfile::load(project_root)?;
+    let overlay_dir = config.overlay.existing_dir(project_root)?;
 
     // ── Step 1: Folder selection ──
     ui::section(1, TOTAL, "📁 Where do you want to install AI-DLC rules?");
-    let (rules_folder, details_parent) = prompt::select_folder()?;
-    ui::step_done(&format!(
-        "Rules     → {rules_folder}/rules/core-workflow.md"
-    ));
+    let mut agents = if agent_args.is_empty() {
+        prompt::select_agents(&config)?
+    } else {
+        agent::from_args(agent_args, &config)?
+    };
+    let details_parent =
+        agent::shared_details_parent(&agents.iter().map(|a| a.as_ref()).collect::<Vec<_>>());
+    let details_parent = &details_parent;
+    for agent in sharing_agents(project_root, &agents, details_parent, &config)? {
+        ui::info(&format!(
+            "{} shares {details_parent}/ with this install and is reinstalled too",
+            agent.label()
+        ));
+        agents.push(agent);
+    }
+    let agent_refs: Vec<&dyn AgentAdapter> = agents.iter().map(|a| a.as_ref()).collect();
+    for agent in &agents {
+        ui::step_done(&format!(
+            "Rules     → {}",
+            agent.core_workflow_path().display()
+        ));
+    }
     ui::step_done(&format!(
         "Details   → {details_parent}/aws-aidlc-rule-details/"
     ));
+    let superseded = superseded_installs(project_root, &agents, details_parent, &config)?;
+    for dir in &superseded {
+        ui::info(&format!(
+            "The earlier install in {dir} will be replaced by the shared one in {details_parent}"
+        ));
+    }
 
-    // Check for existing rules + integrity
-    if extract::rules_exist(&rules_folder, &details_parent) {
-        let modified = integrity::verify_manifest(&details_parent)?;
-        if !modified.is_empty() {
-            ui::warn("These rule files have been modified since last installation:");
-            for f in &modified {
-                println!("      {}", style(format!("• {f}")).yellow());
-            }
-        }
-        if !prompt::confirm_overwrite()? {
+    // Check for existing rules + integrity, including the installs being replaced
+    let replaces_rules = agents
+        .iter()
+        .any(|a| extract::rules_exist(a.rules_folder(), details_parent));
+    let existing = replaces_rules.then_some(details_parent.as_str());
+    for dir in existing
+        .into_iter()
+        .chain(superseded.iter().map(String::as_str))
+    {
+        if !confirm_overwrite(project_root, dir, &policy)? {
             ui::info("Skipped — no changes made.");
             return Ok(());
         }
     }
 
-    // ── Step 2: Download ──
-    ui::section(2, TOTAL, "🌐 Fetching latest AI-DLC rules");
+    // ── Step 2: Commit workflow ──
+    // Asked up front so the commit section is part of the staged install.
+    ui::section(2, TOTAL, "📝 Commit workflow preference");
+    let commit_pref = prompt::select_commit_workflow()?;
+
+    // ── Step 3: Download ──
+    ui::section(3, TOTAL, "🌐 Fetching latest AI-DLC rules");
     let spinner = make_spinner("Contacting GitHub...");
     let client = reqwest::blocking::Client::builder()
         .https_only(true)
@@ -68,12 +178,17 @@ fn run() -> Result<()> {
     spinner.finish_and_clear();
     ui::step_done(&format!("Latest release: {}", style(&release.tag).bold()));
 
+    // Held until extraction finishes so that a parallel run can't delete or rewrite the zip.
+    let spinner = make_spinner("Locking download cache...");
+    let cache_lock = cache::lock(|| {
+        spinner.set_message("Waiting for another process to release the download cache...")
+    })?;
+    spinner.finish_and_clear();
+
     let zip_path = cache::cached_zip_path(&release.tag)?;
 
     if cache::has_cached(&release.tag) {
-        if let Some(expected) = cache::read_checksum(&release.tag)? {
-            download::verify_checksum(&zip_path, &expected)?;
-        }
+        verify_cached_release(&release.tag)?;
         ui::info(&format!(
             "Using cached release {} — skipping download",
             style(&release.tag).bold()
@@ -83,37 +198,119 @@ fn run() -> Result<()> {
         let checksum = download::download_to(&client, &release.zip_url, &zip_path)?;
         spinner.finish_and_clear();
         cache::store_checksum(&release.tag, &checksum)?;
+        let size = std::fs::metadata(&zip_path)?.len();
+        cache::store_metadata(
+            &release.tag,
+            &cache::CacheMetadata::new(&release.zip_url, size, &checksum),
+        )?;
         cache::cleanup_old_versions(&release.tag)?;
         ui::step_done("Downloaded and verified (SHA-256 ✓)");
     }
 
-    // ── Step 3: Install ──
-    ui::section(3, TOTAL, "📂 Installing rules");
+    // ── Step 4: Install ──
+    // Everything is built in a staging directory first and only swapped into the project
+    // once every step has succeeded, so a failure never leaves a half-installed mix.
+    ui::section(4, TOTAL, "📂 Installing rules");
+    let plan = install::InstallPlan {
+        agents: agent_refs.clone(),
+        details_parent,
+        commit_workflow: &commit_pref,
+        release: &release.tag,
+        hash_mode: config.integrity.hash_mode,
+        signing_key: signing_key.as_ref(),
+        patches: &patches,
+        overlay_dir: overlay_dir.as_deref(),
+        overlay_index: config.overlay.index,
+    };
     let spinner = make_spinner("Extracting...");
-    let installed = extract::extract_and_install(&zip_path, &rules_folder, &details_parent)?;
+    let staging = staging::Staging::new(project_root)?;
+    for entry_file in agents.iter().filter_map(|a| a.entry_file()) {
+        staging.copy_in(&entry_file)?;
+    }
+    let output = install::build(&zip_path, &staging.root(), &plan)?;
+    drop(cache_lock);
+    // Superseded installs aren't staged, so committing their targets removes them.
+    let mut targets = plan.targets();
+    for dir in &superseded {
+        targets.extend(install::targets(&[], dir));
+    }
+    let previous = backup::snapshot(project_root, &targets)?;
+    staging.commit(&targets)?;
+    drop(staging);
     spinner.finish_and_clear();
-    ui::step_done(&format!("{} files installed", installed.len()));
 
-    // Patch core-workflow.md paths
-    patch::patch_rule_details_path(&rules_folder, &details_parent)?;
-    ui::step_done("Patched core-workflow.md path references");
+    if let Some(previous) = previous {
+        gitignore::add_to_gitignore(&format!("{}/", backup::BACKUPS_DIR))?;
+        ui::step_done(&format!(
+            "Previous rules backed up as {} (undo with `aidlc-workflows-helper restore {}`)",
+            style(&previous.id).bold(),
+            previous.id
+        ));
+    }
 
-    // Enforce relative paths rule
-    patch::patch_relative_paths_rule(&rules_folder)?;
+    ui::step_done(&format!("{} files installed", output.files.len()));
+    ui::step_done(&format!(
+        "Patched path references in {} files",
+        output.patched_files.len()
+    ));
+    for file in &output.patched_files {
+        println!("      {}", style(format!("• {}", file.display())).dim());
+    }
     ui::step_done("Added relative-paths-only rule to core-workflow.md");
-
-    // Write integrity manifest
-    integrity::write_manifest(&installed, &details_parent)?;
-    ui::step_done("Integrity manifest written");
-
-    // ── Step 4: Commit workflow ──
-    ui::section(4, TOTAL, "📝 Commit workflow preference");
-    let commit_pref = prompt::select_commit_workflow()?;
-    patch::patch_commit_workflow(&rules_folder, &commit_pref)?;
     match commit_pref {
         prompt::CommitWorkflow::None => ui::info("No commit rules added"),
         _ => ui::step_done("Commit workflow patched into core-workflow.md"),
     }
+    for entry_file in agents.iter().filter_map(|a| a.entry_file()) {
+        ui::step_done(&format!(
+            "AI-DLC block added to {} (the rest of the file is untouched)",
+            entry_file.display()
+        ));
+    }
+    if let Some(dir) = &overlay_dir
+        && !output.overlay_files.is_empty()
+    {
+        ui::step_done(&format!(
+            "Merged {} overlay files from {}",
+            output.overlay_files.len(),
+            dir.display()
+        ));
+    }
+    if !patches.is_empty() {
+        ui::step_done(&format!(
+            "Applied {} patches from {}",
+            patches.len(),
+            patchfile::PATCHES_PATH
+        ));
+    }
+    ui::step_done("Integrity manifest written");
+    if signing_key.is_some() {
+        ui::step_done("Integrity manifest signed");
+    } else if policy.required {
+        ui::warn(&format!(
+            "This project requires signed manifests, but no signing key was found — set {} or run `aidlc-workflows-helper sign` with the team key",
+            signing::SIGNING_KEY_ENV
+        ));
+    }
+
+    for agent in &agents {
+        let Some(limit) = agent.max_rule_chars() else {
+            continue;
+        };
+        for (file, chars) in agent::oversized_rules(agent.as_ref(), project_root, &output.files)? {
+            ui::warn(&format!(
+                "{} is {chars} characters; the agent only reads the first {limit} characters of a rule file",
+                file.display()
+            ));
+        }
+    }
+
+    let broken = links::check(project_root, &output.files, details_parent)?;
+    if broken.is_empty() {
+        ui::step_done("All references between rule files resolve");
+    } else {
+        print_broken_links(&broken);
+    }
 
     // ── Step 5: Gitignore ──
     ui::section(5, TOTAL, "🔒 Gitignore configuration");
@@ -123,9 +320,17 @@ fn run() -> Result<()> {
     gitignore::add_to_gitignore("aidlc-docs/aidlc-state.md")?;
     ui::step_done("Auto-added aidlc-docs/audit.md and aidlc-docs/aidlc-state.md to .gitignore");
 
-    if prompt::confirm_gitignore_rules(&rules_folder)? {
-        gitignore::add_to_gitignore(&format!("{rules_folder}/"))?;
-        ui::step_done(&format!("Added {rules_folder}/ to .gitignore"));
+    let mut rules_folders: Vec<&str> = Vec::new();
+    for agent in &agents {
+        if !rules_folders.contains(&agent.rules_folder()) {
+            rules_folders.push(agent.rules_folder());
+        }
+    }
+    for rules_folder in rules_folders {
+        if prompt::confirm_gitignore_rules(rules_folder)? {
+            gitignore::add_to_gitignore(&format!("{rules_folder}/"))?;
+            ui::step_done(&format!("Added {rules_folder}/ to .gitignore"));
+        }
     }
     if prompt::confirm_gitignore_aidlc_docs()? {
         gitignore::add_to_gitignore("aidlc-docs/")?;
@@ -134,9 +339,17 @@ fn run() -> Result<()> {
 
     // ── Step 6: Done ──
     ui::section(6, TOTAL, "🎉 Summary");
-    print_tree(&rules_folder, &details_parent);
+    let core_workflows: Vec<PathBuf> = agents.iter().map(|a| a.core_workflow_path()).collect();
+    print_tree(&core_workflows, details_parent);
     ui::success_box("Installation complete!");
     println!();
+    let mut hints: Vec<String> = Vec::new();
+    for hint in agents.iter().flat_map(|a| a.post_install_hints()) {
+        if !hints.contains(&hint) {
+            ui::info(&hint);
+            hints.push(hint);
+        }
+    }
     ui::info("Start any AI-DLC workflow by telling your AI agent:");
     println!(
         "      {}",
@@ -147,6 +360,558 @@ fn run() -> Result<()> {
     Ok(())
 }
 
+/// Checks the install in `details_parent` against its manifest and asks whether to
+/// overwrite it, offering a diff when files were changed. Returns false to skip.
+fn confirm_overwrite(
+    project_root: &Path,
+    details_parent: &str,
+    policy: &signing::Policy,
+) -> Result<bool> {
+    ui::info(&format!("Existing rules in {details_parent}/:"));
+    let report = integrity::verify_manifest_with(project_root, details_parent, policy)?;
+    if let Some(report) = &report {
+        print_verify_report(report);
+    }
+    let mut offer_diff = report.is_some_and(|r| !r.is_clean());
+    loop {
+        match prompt::confirm_overwrite(offer_diff)? {
+            prompt::OverwriteChoice::Overwrite => return Ok(true),
+            prompt::OverwriteChoice::Skip => return Ok(false),
+            prompt::OverwriteChoice::ShowDiff => {
+                if let Err(e) = print_diffs(project_root, details_parent) {
+                    ui::warn(&format!("Could not show diffs: {e:#}"));
+                }
+                offer_diff = false;
+            }
+        }
+    }
+}
+
+/// Agents of the earlier install in `details_parent` that aren't among `agents`. The
+/// details tree and manifest there are shared, so they are reinstalled along with
+/// `agents` — e.g. `CLAUDE.md` when Cline, which also keeps its details in `.aidlc`, is
+/// installed.
+fn sharing_agents(
+    project_root: &Path,
+    agents: &[Box<dyn AgentAdapter>],
+    details_parent: &str,
+    config: &config::Config,
+) -> Result<Vec<Box<dyn AgentAdapter>>> {
+    let Some(manifest) = integrity::read_manifest(project_root, details_parent)? else {
+        return Ok(Vec::new());
+    };
+    let installed = manifest.agents(config)?.unwrap_or_default();
+    Ok(installed
+        .into_iter()
+        .filter(|old| {
+            !agents
+                .iter()
+                .any(|a| a.id() == old.id() && a.rules_folder() == old.rules_folder())
+        })
+        .collect())
+}
+
+/// Details parents of earlier installs that the install for `agents` into
+/// `details_parent` replaces: a manifest elsewhere whose agents are all among `agents`,
+/// e.g. a Kiro-only install in `.kiro` when Kiro and Cursor now share `.aidlc`.
+fn superseded_installs(
+    project_root: &Path,
+    agents: &[Box<dyn AgentAdapter>],
+    details_parent: &str,
+    config: &config::Config,
+) -> Result<Vec<String>> {
+    let mut dirs: Vec<String> = Vec::new();
+    for agent in agents {
+        let dir = agent.details_parent();
+        if dir == details_parent || dirs.iter().any(|d| d == dir) {
+            continue;
+        }
+        let Some(manifest) = integrity::read_manifest(project_root, dir)? else {
+            continue;
+        };
+        let covered = manifest.agents(config)?.is_some_and(|installed| {
+            installed.iter().all(|old| {
+                agents
+                    .iter()
+                    .any(|a| a.id() == old.id() && a.rules_folder() == old.rules_folder())
+            })
+        });
+        if covered {
+            dirs.push(dir.to_string());
+        }
+    }
+    Ok(dirs)
+}
+
+/// Warns about every modified, missing and unexpected rule file in `report`, and about a
+/// missing or invalid manifest signature.
+fn print_verify_report(report: &integrity::VerifyReport) {
+    match report.signature {
+        signing::SignatureStatus::Invalid => ui::warn(
+            "The integrity manifest signature is invalid — the manifest may have been regenerated without the team key",
+        ),
+        signing::SignatureStatus::Missing if report.signature_required => {
+            ui::warn("The integrity manifest is not signed, but this project requires it")
+        }
+        _ => {}
+    }
+    let groups = [
+        (
+            &report.modified,
+            "These rule files have been modified since last installation:",
+        ),
+        (
+            &report.missing,
+            "These rule files have been deleted since last installation:",
+        ),
+        (
+            &report.unexpected,
+            "These files were not part of the last installation:",
+        ),
+    ];
+    for (files, heading) in groups {
+        if files.is_empty() {
+            continue;
+        }
+        ui::warn(heading);
+        for f in files {
+            println!("      {}", style(format!("• {f}")).yellow());
+        }
+    }
+    if !report.line_endings.is_empty() {
+        ui::info("Only line endings differ in these rule files (not treated as modified):");
+        for f in &report.line_endings {
+            println!("      {}", style(format!("• {f}")).dim());
+        }
+    }
+}
+
+/// Checks the cached zip for `tag` against its recorded checksum and size.
+fn verify_cached_release(tag: &str) -> Result<()> {
+    let zip_path = cache::cached_zip_path(tag)?;
+    let metadata = cache::read_metadata(tag)?;
+    if let Some(expected) = cache::read_checksum(tag)? {
+        download::verify_checksum(&zip_path, &expected, metadata.map(|m| m.size))?;
+    }
+    Ok(())
+}
+
+/// Folders to look for integrity manifests in: the one given on the command line, or the
+/// details parent of every built-in agent.
+fn manifest_dirs(details_parent: Option<String>) -> Vec<String> {
+    match details_parent {
+        Some(dir) => vec![dir],
+        None => {
+            let mut dirs: Vec<String> = Vec::new();
+            for agent in agent::built_in(&config::Config::default()) {
+                if !dirs.iter().any(|d| d == agent.details_parent()) {
+                    dirs.push(agent.details_parent().to_string());
+                }
+            }
+            dirs
+        }
+    }
+}
+
+/// Verifies every manifest found (or the one under `details_parent`) and maps the most
+/// severe outcome to an exit code — see [`cli::Command::Verify`].
+fn run_verify(details_parent: Option<String>, github_annotations: bool) -> Result<ExitCode> {
+    let project_root = Path::new(".");
+    let candidates = manifest_dirs(details_parent);
+    let config = config::load(project_root)?;
+    let policy = signing::Policy::load(project_root, &config.integrity)?;
+
+    let mut worst: Option<integrity::VerifyStatus> = None;
+    for dir in &candidates {
+        let Some(report) = integrity::verify_manifest_with(project_root, dir, &policy)? else {
+            continue;
+        };
+        if report.is_clean() {
+            ui::step_done(&format!(
+                "{dir}: all rule files match the integrity manifest"
+            ));
+        }
+        if report.signature == signing::SignatureStatus::Valid {
+            ui::step_done(&format!("{dir}: manifest signature is valid"));
+        }
+        print_verify_report(&report);
+        if github_annotations {
+            print_github_annotations(dir, &report);
+        }
+        worst = Some(worst.map_or(report.status(), |w| w.min(report.status())));
+    }
+
+    let status = worst.unwrap_or(integrity::VerifyStatus::NoManifest);
+    if status == integrity::VerifyStatus::NoManifest {
+        ui::warn(&format!(
+            "No integrity manifest found in {}",
+            candidates.join(", ")
+        ));
+        if github_annotations {
+            println!("::error::No AI-DLC integrity manifest found");
+        }
+    }
+
+    Ok(ExitCode::from(match status {
+        integrity::VerifyStatus::Clean => 0,
+        integrity::VerifyStatus::Modified => 3,
+        integrity::VerifyStatus::Missing => 4,
+        integrity::VerifyStatus::NoManifest => 5,
+        integrity::VerifyStatus::BadSignature => 6,
+    }))
+}
+
+/// Checks the references in every installation found (or the one under
+/// `details_parent`). Exits non-zero when any reference is dangling.
+fn run_check_links(details_parent: Option<String>) -> Result<ExitCode> {
+    let project_root = Path::new(".");
+    let candidates = manifest_dirs(details_parent);
+    let mut found = false;
+    let mut all_broken = Vec::new();
+    for dir in &candidates {
+        let Some(manifest) = integrity::read_manifest(project_root, dir)? else {
+            continue;
+        };
+        found = true;
+        let files: Vec<PathBuf> = manifest
+            .files
+            .iter()
+            .map(|f| PathBuf::from(&f.path))
+            .collect();
+        let broken = links::check(project_root, &files, dir)?;
+        if broken.is_empty() {
+            ui::step_done(&format!("{dir}: all references between rule files resolve"));
+        }
+        all_broken.extend(broken);
+    }
+    if !found {
+        bail!("No integrity manifest found in {}", candidates.join(", "));
+    }
+    if all_broken.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    print_broken_links(&all_broken);
+    Ok(ExitCode::from(7))
+}
+
+/// Warns about every dangling reference, as `file:line → target`.
+fn print_broken_links(broken: &[links::BrokenLink]) {
+    ui::warn("These references in the rule files point at nothing:");
+    for link in broken {
+        println!(
+            "      {}",
+            style(format!("• {}:{} → {}", link.file, link.line, link.target)).yellow()
+        );
+    }
+}
+
+/// Generates a signing key pair: the public key goes into the project (to be committed),
+/// the private key into the user's config directory.
+fn run_keygen(force: bool) -> Result<()> {
+    let project_root = Path::new(".");
+    let config = config::load(project_root)?;
+    let public_path = PathBuf::from(
+        config
+            .integrity
+            .public_key
+            .as_deref()
+            .unwrap_or(signing::DEFAULT_PUBLIC_KEY_PATH),
+    );
+    let private_path = match std::env::var_os(signing::SIGNING_KEY_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => signing::default_private_key_path()?,
+    };
+    if !force {
+        for path in [&private_path, &public_path] {
+            if path.exists() {
+                bail!(
+                    "{} already exists — pass --force to replace it",
+                    path.display()
+                );
+            }
+        }
+    }
+
+    let key = signing::generate_key()?;
+    signing::write_private_key(&private_path, &key)?;
+    signing::write_public_key(&public_path, &key.verifying_key())?;
+    ui::step_done(&format!(
+        "Private key written to {}",
+        private_path.display()
+    ));
+    ui::step_done(&format!("Public key written to {}", public_path.display()));
+    ui::info("Keep the private key secret and commit the public key.");
+    ui::info(
+        "Add `require_signature = true` under [integrity] in .aidlc-helper/config.toml to enforce signatures.",
+    );
+    Ok(())
+}
+
+/// Signs every installed manifest (or the one under `details_parent`), refusing to sign
+/// an installation whose files no longer match it.
+fn run_sign(details_parent: Option<String>) -> Result<()> {
+    let project_root = Path::new(".");
+    let key = signing::find_signing_key()?.with_context(|| {
+        format!(
+            "No signing key found — set {} or run `aidlc-workflows-helper keygen`",
+            signing::SIGNING_KEY_ENV
+        )
+    })?;
+    let config = config::load(project_root)?;
+    signing::Policy::load(project_root, &config.integrity)?.check_key(&key)?;
+    let candidates = manifest_dirs(details_parent);
+    let mut found = false;
+    for dir in &candidates {
+        let Some(report) = integrity::verify_manifest(project_root, dir)? else {
+            continue;
+        };
+        found = true;
+        if !report.is_clean() {
+            print_verify_report(&report);
+            bail!("{dir}: installed files don't match the manifest — refusing to sign");
+        }
+        signing::sign_manifest(project_root, &integrity::manifest_path(dir), &key)?;
+        ui::step_done(&format!("{dir}: integrity manifest signed"));
+    }
+    if !found {
+        bail!("No integrity manifest found in {}", candidates.join(", "));
+    }
+    Ok(())
+}
+
+/// Removes every installation found (or the one under `details_parent`), backing it up
+/// first. The agent's entry file keeps everything but the helper's block.
+fn run_uninstall(details_parent: Option<String>) -> Result<()> {
+    let project_root = Path::new(".");
+    let config = config::load(project_root)?;
+    let candidates = manifest_dirs(details_parent);
+    let mut found = false;
+    for dir in &candidates {
+        let Some(manifest) = integrity::read_manifest(project_root, dir)? else {
+            continue;
+        };
+        found = true;
+        let agents = manifest.agents(&config)?.with_context(|| {
+            format!(
+                "The manifest in {dir} was written by an older version — remove the rules by hand"
+            )
+        })?;
+        let agent_refs: Vec<&dyn AgentAdapter> = agents.iter().map(|a| a.as_ref()).collect();
+        let targets = install::targets(&agent_refs, dir);
+        let previous = backup::snapshot(project_root, &targets)?;
+        let staging = staging::Staging::new(project_root)?;
+        for agent in &agents {
+            if let Some(entry_file) = agent.entry_file() {
+                staging.copy_in(&entry_file)?;
+                agent::remove_entry_block(agent.as_ref(), &staging.root())?;
+            }
+        }
+        staging.commit(&targets)?;
+        drop(staging);
+        // Folders left empty by the removal; `remove_dir` leaves non-empty ones alone.
+        for folder in agents
+            .iter()
+            .map(|a| a.rules_folder())
+            .chain([dir.as_str()])
+        {
+            std::fs::remove_dir(folder).ok();
+        }
+
+        ui::step_done(&format!("{dir}: AI-DLC rules removed"));
+        for entry_file in agents.iter().filter_map(|a| a.entry_file()) {
+            ui::step_done(&format!(
+                "AI-DLC block removed from {}",
+                entry_file.display()
+            ));
+        }
+        if let Some(previous) = previous {
+            gitignore::add_to_gitignore(&format!("{}/", backup::BACKUPS_DIR))?;
+            ui::info(&format!(
+                "Undo with `aidlc-workflows-helper restore {}`",
+                previous.id
+            ));
+        }
+    }
+    if !found {
+        bail!("No integrity manifest found in {}", candidates.join(", "));
+    }
+    Ok(())
+}
+
+/// Prints diffs for every installation found (or the one under `details_parent`).
+fn run_diff(details_parent: Option<String>) -> Result<()> {
+    let project_root = Path::new(".");
+    let candidates = manifest_dirs(details_parent);
+    let mut found = false;
+    for dir in &candidates {
+        if integrity::read_manifest(project_root, dir)?.is_none() {
+            continue;
+        }
+        found = true;
+        print_diffs(project_root, dir)?;
+    }
+    if !found {
+        bail!("No integrity manifest found in {}", candidates.join(", "));
+    }
+    Ok(())
+}
+
+/// Rebuilds the release recorded in the manifest under `details_parent` from the download
+/// cache and prints a colorized diff for every file that differs from it.
+fn print_diffs(project_root: &Path, details_parent: &str) -> Result<()> {
+    let manifest = integrity::read_manifest(project_root, details_parent)?
+        .with_context(|| format!("No integrity manifest found in {details_parent}"))?;
+    let config = config::load(project_root)?;
+    let patches = patchfile::load(project_root)?;
+    let overlay_dir = config.overlay.existing_dir(project_root)?;
+    let older_version = || {
+        format!(
+            "The manifest in {details_parent} was written by an older version — reinstall to enable diffs"
+        )
+    };
+    let agents = manifest.agents(&config)?.with_context(older_version)?;
+    if patchfile::fingerprint(&patches) != manifest.patches {
+        bail!(
+            "{} changed since the rules in {details_parent} were installed, so their pristine copy can't be rebuilt — reinstall to diff against the current patches",
+            patchfile::PATCHES_PATH
+        );
+    }
+    let plan = install::InstallPlan {
+        patches: &patches,
+        overlay_dir: overlay_dir.as_deref(),
+        overlay_index: config.overlay.index,
+        ..install::InstallPlan::from_manifest(&manifest, details_parent, &agents)
+            .with_context(older_version)?
+    };
+
+    let _cache_lock =
+        cache::lock(|| ui::info("Waiting for another process to release the download cache..."))?;
+    if !cache::has_cached(plan.release) {
+        bail!(
+            "Release {} is no longer in the download cache — reinstall to fetch it again",
+            plan.release
+        );
+    }
+    verify_cached_release(plan.release)?;
+    let zip_path = cache::cached_zip_path(plan.release)?;
+
+    let diffs = diff::collect(project_root, &plan, &zip_path)?;
+    if diffs.is_empty() {
+        ui::step_done(&format!(
+            "{details_parent}: no differences from release {}",
+            plan.release
+        ));
+    }
+    for file_diff in &diffs {
+        println!();
+        diff::print_colored(file_diff);
+    }
+    Ok(())
+}
+
+/// Prints one GitHub Actions `::error` workflow command per offending file, plus one for
+/// a required signature that is missing or invalid.
+fn print_github_annotations(details_parent: &str, report: &integrity::VerifyReport) {
+    if report.signature_failed() {
+        let manifest = integrity::manifest_path(details_parent);
+        println!(
+            "::error file={}::AI-DLC integrity manifest signature is {}",
+            escape_annotation_property(&manifest.to_string_lossy().replace('\\', "/")),
+            if report.signature == signing::SignatureStatus::Missing {
+                "missing"
+            } else {
+                "invalid"
+            }
+        );
+    }
+    let groups = [
+        (
+            &report.modified,
+            "AI-DLC rule file modified since installation",
+        ),
+        (
+            &report.missing,
+            "AI-DLC rule file deleted since installation",
+        ),
+        (
+            &report.unexpected,
+            "File not part of the AI-DLC installation",
+        ),
+    ];
+    for (files, message) in groups {
+        for f in files {
+            println!("::error file={}::{message}", escape_annotation_property(f));
+        }
+    }
+    for f in &report.line_endings {
+        println!(
+            "::notice file={}::Only line endings differ from the AI-DLC installation",
+            escape_annotation_property(f)
+        );
+    }
+}
+
+/// Escapes a value for use as a GitHub Actions workflow command property.
+fn escape_annotation_property(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Lists backups, or restores one chosen by id or interactively.
+fn run_restore(list: bool, id: Option<String>) -> Result<()> {
+    let project_root = Path::new(".");
+    let backups = backup::list(project_root)?;
+    if backups.is_empty() {
+        ui::info("No backups found — they are created whenever rules are overwritten.");
+        return Ok(());
+    }
+
+    let labels: Vec<String> = backups.iter().map(describe_backup).collect();
+    if list {
+        println!("  {}", style("Backups (newest first):").dim());
+        for label in &labels {
+            println!("      • {label}");
+        }
+        return Ok(());
+    }
+
+    let id = match id {
+        Some(id) => id,
+        None => backups[prompt::select_backup(&labels)?].id.clone(),
+    };
+    let (restored, previous) = backup::restore(project_root, &id)?;
+    for target in &restored.targets {
+        ui::step_done(&format!("Restored {}", target.display()));
+    }
+    ui::success_box(&format!("Backup {id} restored!"));
+    if let Some(previous) = previous {
+        ui::info(&format!(
+            "The replaced rules were backed up as {} (undo with `aidlc-workflows-helper restore {}`)",
+            style(&previous.id).bold(),
+            previous.id
+        ));
+    }
+    Ok(())
+}
+
+/// One-line description of a backup: its id followed by the paths it holds.
+fn describe_backup(backup: &backup::Backup) -> String {
+    let targets: Vec<String> = backup
+        .targets
+        .iter()
+        .map(|t| t.display().to_string())
+        .collect();
+    format!(
+        "{}  {}",
+        style(&backup.id).bold(),
+        style(targets.join(", ")).dim()
+    )
+}
+
 /// Creates an animated spinner for long-running operations (download, extraction).
 /// Call `.finish_and_clear()` when the operation completes.
 fn make_spinner(msg: &str) -> ProgressBar {
@@ -162,11 +927,14 @@ fn make_spinner(msg: &str) -> ProgressBar {
 }
 
 /// Prints a visual tree of the installed file structure.
-fn print_tree(rules_folder: &str, details_parent: &str) {
+fn print_tree(core_workflows: &[PathBuf], details_parent: &str) {
     println!("  {}", style("Installed:").dim());
-    println!("  {}/", style(rules_folder).bold());
-    println!("  └── rules/");
-    println!("      └── core-workflow.md");
+    for core_workflow in core_workflows {
+        if let (Some(dir), Some(name)) = (core_workflow.parent(), core_workflow.file_name()) {
+            println!("  {}/", style(dir.display()).bold());
+            println!("  └── {}", name.to_string_lossy());
+        }
+    }
     println!("  {}/", style(details_parent).bold());
     println!("  └── aws-aidlc-rule-details/");
     println!("      ├── common/");
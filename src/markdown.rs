@@ -0,0 +1,351 @@
+//! Markdown patch engine.
+//!
+//! Rule files are parsed with `pulldown-cmark` to locate sections and code blocks, and
+//! edits are spliced into the original text by byte range — everything outside the edited
+//! section is left exactly as it was.
+//!
+//! A section is a heading plus everything up to the next heading of the same or a higher
+//! level. Sections are addressed by heading path: heading titles joined with ` > `, e.g.
+//! `"Adaptive Workflow Principle > Stages"`. A path matches a section whose own title is
+//! the last segment and whose enclosing sections include the other segments, in order;
+//! levels in between may be skipped, so the path doesn't need to start at the document
+//! title. When that matches several sections, the one whose enclosing headings end with
+//! exactly the given segments wins. Headings inside fenced code blocks aren't sections.
+
+use anyhow::{Result, bail};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Separator between the headings of a heading path.
+pub const PATH_SEPARATOR: &str = " > ";
+
+/// Where [`insert`] puts a block relative to the target section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Position {
+    /// Just before the section's heading.
+    Before,
+    /// Just after the section, including its subsections.
+    After,
+    /// At the end of the section's own content, before its first subsection.
+    Inside,
+}
+
+/// A heading and the text it governs, as byte offsets into the document.
+#[derive(Debug)]
+struct Section {
+    /// Titles of the enclosing sections, outermost first, ending with this one.
+    path: Vec<String>,
+    /// Start of the heading line.
+    start: usize,
+    /// End of the section's own content: the next heading of any level.
+    own_end: usize,
+    /// End of the section including its subsections.
+    end: usize,
+}
+
+/// Sections and fenced code blocks of a markdown document.
+struct Outline {
+    sections: Vec<Section>,
+    code_blocks: Vec<Range<usize>>,
+}
+
+impl Outline {
+    fn parse(content: &str) -> Self {
+        let mut headings: Vec<(usize, usize, String)> = Vec::new();
+        let mut code_blocks = Vec::new();
+        let mut current: Option<(usize, usize, String)> = None;
+
+        for (event, range) in Parser::new(content).into_offset_iter() {
+            match event {
+                Event::Start(Tag::Heading { level, .. }) => {
+                    current = Some((
+                        level as usize,
+                        line_start(content, range.start),
+                        String::new(),
+                    ));
+                }
+                Event::Text(text) | Event::Code(text) => {
+                    if let Some((_, _, title)) = &mut current {
+                        title.push_str(&text);
+                    }
+                }
+                Event::End(TagEnd::Heading(_)) => {
+                    if let Some((level, start, title)) = current.take() {
+                        headings.push((level, start, title.trim().to_string()));
+                    }
+                }
+                Event::Start(Tag::CodeBlock(_)) => code_blocks.push(range),
+                _ => {}
+            }
+        }
+
+        let mut sections = Vec::new();
+        let mut stack: Vec<(usize, String)> = Vec::new();
+        for (i, (level, start, title)) in headings.iter().enumerate() {
+            while stack.last().is_some_and(|(l, _)| l >= level) {
+                stack.pop();
+            }
+            stack.push((*level, title.clone()));
+            let next_start = |stop: &dyn Fn(usize) -> bool| {
+                headings[i + 1..]
+                    .iter()
+                    .find(|(l, _, _)| stop(*l))
+                    .map_or(content.len(), |(_, s, _)| *s)
+            };
+            sections.push(Section {
+                path: stack.iter().map(|(_, t)| t.clone()).collect(),
+                start: *start,
+                own_end: next_start(&|_| true),
+                end: next_start(&|l| l <= *level),
+            });
+        }
+        Self {
+            sections,
+            code_blocks,
+        }
+    }
+
+    /// The single section matching `heading_path`.
+    fn find(&self, heading_path: &str) -> Result<&Section> {
+        let wanted: Vec<&str> = heading_path.split(PATH_SEPARATOR).map(str::trim).collect();
+        let mut matches: Vec<&Section> = self
+            .sections
+            .iter()
+            .filter(|s| path_matches(&s.path, &wanted))
+            .collect();
+        if matches.len() > 1 {
+            let exact: Vec<&Section> = matches
+                .iter()
+                .copied()
+                .filter(|s| {
+                    s.path.len() >= wanted.len()
+                        && s.path[s.path.len() - wanted.len()..]
+                            .iter()
+                            .zip(&wanted)
+                            .all(|(title, w)| title == w)
+                })
+                .collect();
+            if exact.len() == 1 {
+                matches = exact;
+            }
+        }
+        match matches.as_slice() {
+            [section] => Ok(section),
+            [] => bail!("No section matches heading path \"{heading_path}\""),
+            _ => bail!(
+                "Heading path \"{heading_path}\" matches {} sections — add a parent heading to disambiguate",
+                matches.len()
+            ),
+        }
+    }
+}
+
+/// Insert `block` relative to the section at `heading_path`.
+pub fn insert(
+    content: &str,
+    heading_path: &str,
+    position: Position,
+    block: &str,
+) -> Result<String> {
+    let at = offset(content, heading_path, position)?;
+    Ok(splice(content, at..at, block))
+}
+
+/// Byte offset of `position` relative to the section at `heading_path`.
+pub fn offset(content: &str, heading_path: &str, position: Position) -> Result<usize> {
+    let outline = Outline::parse(content);
+    let section = outline.find(heading_path)?;
+    Ok(match position {
+        Position::Before => section.start,
+        Position::After => section.end,
+        Position::Inside => section.own_end,
+    })
+}
+
+/// Replace the section at `heading_path`, heading and subsections included, with `block`.
+pub fn replace_section(content: &str, heading_path: &str, block: &str) -> Result<String> {
+    let outline = Outline::parse(content);
+    let section = outline.find(heading_path)?;
+    Ok(splice(content, section.start..section.end, block))
+}
+
+/// Delete the section at `heading_path`, heading and subsections included.
+pub fn delete_section(content: &str, heading_path: &str) -> Result<String> {
+    let outline = Outline::parse(content);
+    let section = outline.find(heading_path)?;
+    Ok(splice(content, section.start..section.end, ""))
+}
+
+/// Title of the first heading in `content`, as heading paths spell it.
+pub fn title(content: &str) -> Option<String> {
+    Outline::parse(content)
+        .sections
+        .into_iter()
+        .next()
+        .and_then(|section| section.path.last().cloned())
+}
+
+/// Apply `f` to every stretch of `content` outside fenced and indented code blocks.
+/// Inline code spans are prose for this purpose — they are how rule files quote paths.
+pub fn map_prose(content: &str, f: impl Fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    for block in Outline::parse(content).code_blocks {
+        out.push_str(&f(&content[last..block.start]));
+        out.push_str(&content[block.clone()]);
+        last = block.end;
+    }
+    out.push_str(&f(&content[last..]));
+    out
+}
+
+/// Byte ranges of the fenced and indented code blocks in `content`.
+pub fn code_blocks(content: &str) -> Vec<Range<usize>> {
+    Outline::parse(content).code_blocks
+}
+
+/// Replace `range` with `block`, keeping exactly one blank line between `block` and the
+/// text on either side. An empty `block` removes the range.
+pub fn splice(content: &str, range: Range<usize>, block: &str) -> String {
+    let before = content[..range.start].trim_end_matches('\n');
+    let after = content[range.end..].trim_start_matches('\n');
+    let parts: Vec<&str> = [before, block.trim_matches('\n'), after]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+    let mut out = parts.join("\n\n");
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+fn line_start(content: &str, offset: usize) -> usize {
+    content[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// True when `wanted` ends with the section's own title and its other segments appear
+/// among the enclosing titles in order.
+fn path_matches(path: &[String], wanted: &[&str]) -> bool {
+    let Some((last, parents)) = wanted.split_last() else {
+        return false;
+    };
+    let Some((own, ancestors)) = path.split_last() else {
+        return false;
+    };
+    if own != last {
+        return false;
+    }
+    let mut ancestors = ancestors.iter();
+    parents
+        .iter()
+        .all(|parent| ancestors.any(|ancestor| ancestor == parent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "\
+# Core Workflow
+
+Intro.
+
+## Adaptive Workflow Principle
+
+Adapt.
+
+### Stages
+
+- Inception
+
+### Depth
+
+Some depth.
+
+## Stages
+
+Top-level stages.
+
+```markdown
+## Not A Heading
+```
+";
+
+    #[test]
+    fn inserts_before_after_and_inside_sections() {
+        let before = insert(
+            DOC,
+            "Adaptive Workflow Principle > Stages",
+            Position::Before,
+            "NEW",
+        )
+        .unwrap();
+        assert!(before.contains("Adapt.\n\nNEW\n\n### Stages\n"));
+
+        let after = insert(DOC, "Adaptive Workflow Principle", Position::After, "NEW").unwrap();
+        assert!(after.contains("Some depth.\n\nNEW\n\n## Stages\n"));
+
+        let inside = insert(DOC, "Adaptive Workflow Principle", Position::Inside, "NEW").unwrap();
+        assert!(inside.contains("Adapt.\n\nNEW\n\n### Stages\n"));
+
+        let at_end = insert(DOC, "Core Workflow > Stages", Position::After, "NEW").unwrap();
+        assert!(at_end.ends_with("```\n\nNEW\n"));
+    }
+
+    #[test]
+    fn replaces_and_deletes_sections_with_their_subsections() {
+        let replaced =
+            replace_section(DOC, "Adaptive Workflow Principle", "## Replaced\n\nBody.").unwrap();
+        assert!(replaced.contains("Intro.\n\n## Replaced\n\nBody.\n\n## Stages\n"));
+        assert!(!replaced.contains("### Depth"));
+
+        let deleted = delete_section(DOC, "Adaptive Workflow Principle > Depth").unwrap();
+        assert!(deleted.contains("- Inception\n\n## Stages\n"));
+        assert!(!deleted.contains("Some depth."));
+    }
+
+    #[test]
+    fn title_is_the_first_heading() {
+        assert_eq!(title(DOC).as_deref(), Some("Core Workflow"));
+        assert_eq!(title("## Use `x`\nBody.").as_deref(), Some("Use x"));
+        assert_eq!(title("No heading."), None);
+    }
+
+    #[test]
+    fn heading_paths_must_match_exactly_one_section() {
+        let ambiguous = insert(DOC, "Stages", Position::Before, "NEW").unwrap_err();
+        // "Core Workflow > Stages" also matches the nested one, but only the top-level
+        // section is a direct child.
+        assert!(delete_section(DOC, "Core Workflow > Stages").is_ok());
+        assert!(ambiguous.to_string().contains("matches 2 sections"));
+        assert!(delete_section(DOC, "Missing").is_err());
+        assert!(delete_section(DOC, "Not A Heading").is_err());
+        assert!(delete_section(DOC, "Depth > Stages").is_err());
+    }
+
+    #[test]
+    fn map_prose_skips_code_blocks() {
+        let content = "Use `a/b`.\n\n```\na/b\n```\n\n    a/b\n\nThen a/b.\n";
+        let mapped = map_prose(content, |text| text.replace("a/b", "x/y"));
+        assert_eq!(
+            mapped,
+            "Use `x/y`.\n\n```\na/b\n```\n\n    a/b\n\nThen x/y.\n"
+        );
+    }
+
+    #[test]
+    fn splice_keeps_one_blank_line_around_the_block() {
+        let content = "Intro.\n\nOld.\n\n\nOutro.\n";
+        let start = content.find("Old.").unwrap();
+        let range = start..start + "Old.\n".len();
+        assert_eq!(
+            splice(content, range.clone(), "New."),
+            "Intro.\n\nNew.\n\nOutro.\n"
+        );
+        assert_eq!(splice(content, range, ""), "Intro.\n\nOutro.\n");
+        assert_eq!(splice("", 0..0, "New."), "New.\n");
+    }
+}
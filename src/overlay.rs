@@ -0,0 +1,135 @@
+//! Team overlay merged on top of the upstream rule details.
+//!
+//! The overlay folder (`.aidlc-overlay/` by default) mirrors the layout of
+//! `aws-aidlc-rule-details/`: `.aidlc-overlay/common/security-checklist.md` is installed as
+//! `<details_parent>/aws-aidlc-rule-details/common/security-checklist.md`, replacing the
+//! upstream file of the same name if there is one. Overlay files are recorded with
+//! `"source": "overlay"` in the integrity manifest.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default overlay location, relative to the project root.
+pub const DEFAULT_OVERLAY_DIR: &str = ".aidlc-overlay";
+
+/// Copy every file of `overlay_dir` into the details tree under `root`.
+///
+/// Returns the installed paths, relative to `root`, sorted.
+pub fn apply(overlay_dir: &Path, root: &Path, details_parent: &str) -> Result<Vec<PathBuf>> {
+    let details = Path::new(details_parent).join("aws-aidlc-rule-details");
+    let mut installed = Vec::new();
+    for file in files(overlay_dir)? {
+        let target = details.join(&file);
+        let dest = root.join(&target);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(overlay_dir.join(&file), &dest)
+            .with_context(|| format!("Failed to copy overlay file {}", file.display()))?;
+        installed.push(target);
+    }
+    Ok(installed)
+}
+
+/// The overlay's files, relative to `overlay_dir`, sorted. Symlinks are rejected so an
+/// overlay can't pull in files from elsewhere on the machine.
+fn files(overlay_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    collect(overlay_dir, Path::new(""), &mut out)?;
+    out.sort();
+    Ok(out)
+}
+
+fn collect(base: &Path, dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(base.join(dir))? {
+        let entry = entry?;
+        let rel = dir.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            bail!(
+                "Overlay contains a symlink, which is not supported: {}",
+                base.join(&rel).display()
+            );
+        } else if file_type.is_dir() {
+            collect(base, &rel, out)?;
+        } else {
+            out.push(rel);
+        }
+    }
+    Ok(())
+}
+
+/// Body of the core-workflow.md section listing the installed overlay files.
+pub fn index_section(installed: &[PathBuf]) -> String {
+    let mut section = String::from(
+        "## Team Rule Details\n\
+         **MANDATORY**: This project adds its own rule details on top of AI-DLC. Load the \
+         relevant ones alongside the standard rule details:\n",
+    );
+    for path in installed {
+        section.push_str(&format!(
+            "- `{}`\n",
+            path.to_string_lossy().replace('\\', "/")
+        ));
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_overlay_into_details_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let overlay = dir.path().join(".aidlc-overlay");
+        fs::create_dir_all(overlay.join("common")).unwrap();
+        fs::write(overlay.join("common/security-checklist.md"), "# Security").unwrap();
+        fs::write(overlay.join("common/overview.md"), "# Team overview").unwrap();
+        let root = dir.path().join("staged");
+        let details = root.join(".kiro/aws-aidlc-rule-details/common");
+        fs::create_dir_all(&details).unwrap();
+        fs::write(details.join("overview.md"), "# Upstream overview").unwrap();
+
+        let installed = apply(&overlay, &root, ".kiro").unwrap();
+
+        assert_eq!(
+            installed,
+            [
+                PathBuf::from(".kiro/aws-aidlc-rule-details/common/overview.md"),
+                PathBuf::from(".kiro/aws-aidlc-rule-details/common/security-checklist.md"),
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(details.join("overview.md")).unwrap(),
+            "# Team overview"
+        );
+        assert_eq!(
+            fs::read_to_string(details.join("security-checklist.md")).unwrap(),
+            "# Security"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let overlay = dir.path().join(".aidlc-overlay");
+        fs::create_dir_all(&overlay).unwrap();
+        std::os::unix::fs::symlink("/etc/hostname", overlay.join("host.md")).unwrap();
+
+        assert!(apply(&overlay, &dir.path().join("staged"), ".kiro").is_err());
+    }
+
+    #[test]
+    fn index_lists_installed_paths() {
+        let section = index_section(&[PathBuf::from(
+            ".kiro/aws-aidlc-rule-details/common/security-checklist.md",
+        )]);
+        assert!(section.starts_with("## Team Rule Details\n"));
+        assert!(
+            section.ends_with("- `.kiro/aws-aidlc-rule-details/common/security-checklist.md`\n")
+        );
+    }
+}
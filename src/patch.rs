@@ -1,64 +1,137 @@
-//! Patches `core-workflow.md` after installation.
+//! Patches installed rule files after extraction.
 //!
 //! Two kinds of patches are applied:
-//! 1. **Path references** — The upstream `core-workflow.md` hardcodes paths like
-//!    `.kiro/aws-aidlc-rule-details/`. These are replaced with the user's actual folder.
-//! 2. **Commit workflow** — Optionally appends a commit convention section (Conventional
-//!    Commits or free-form) based on the user's preference.
+//! 1. **Path references** — Upstream rule files hardcode paths like
+//!    `.kiro/aws-aidlc-rule-details/`, both in `core-workflow.md` and in the details files
+//!    that cross-reference each other. Every `<folder>/aws-aidlc-rule-details/` reference
+//!    in every installed markdown file, whatever the folder, is replaced with the user's
+//!    actual folder.
+//! 2. **Helper sections** — The commit convention section (Conventional Commits or
+//!    free-form) and the relative-paths rule, added to `core-workflow.md` around the
+//!    "Adaptive Workflow Principle" section: the relative-paths rule just before it, the
+//!    others just after it in the order they were first applied. A file without that
+//!    section gets them under its title, or at the end if it has no headings.
+//!
+//! Every section the helper adds is wrapped in `<!-- aidlc-helper:begin name -->` /
+//! `<!-- aidlc-helper:end name -->` markers, so re-running a patch replaces the section in
+//! place instead of appending a duplicate, and a section can be removed by name. An
+//! unmarked section with the same heading — the rule written in by hand, say — is taken
+//! over rather than duplicated.
+//!
+//! Edits go through the [`crate::markdown`] engine, so sections are found by heading path
+//! and fenced code blocks — which quote paths and markers as examples — are never touched.
 
+use crate::markdown::{self, Position};
 use crate::prompt::CommitWorkflow;
 use anyhow::{Context, Result};
+use regex::Regex;
 use std::fs;
-use std::path::Path;
-
-/// Default paths that appear in core-workflow.md and need replacing.
-const DEFAULT_PATHS: &[&str] = &[
-    ".kiro/aws-aidlc-rule-details/",
-    ".amazonq/aws-aidlc-rule-details/",
-    ".aiassistant/aws-aidlc-rule-details/",
-];
-
-/// Patch core-workflow.md to replace default rule-details paths with the user's chosen path.
-pub fn patch_rule_details_path(rules_folder: &str, details_parent: &str) -> Result<()> {
-    let workflow_path = Path::new(rules_folder).join("rules/core-workflow.md");
-    if !workflow_path.exists() {
-        return Ok(());
-    }
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 
-    let mut content =
-        fs::read_to_string(&workflow_path).context("Failed to read core-workflow.md")?;
+const COMMIT_WORKFLOW_SECTION_NAME: &str = "commit-workflow";
+const RELATIVE_PATHS_SECTION_NAME: &str = "relative-paths";
+const OVERLAY_INDEX_SECTION_NAME: &str = "overlay-index";
 
-    let replacement = format!("{details_parent}/aws-aidlc-rule-details/");
-    for default in DEFAULT_PATHS {
-        content = content.replace(default, &replacement);
-    }
+/// Heading path of the core-workflow.md section the helper sections are placed around.
+const HELPER_SECTIONS_ANCHOR: &str = "Adaptive Workflow Principle";
 
-    fs::write(&workflow_path, content)?;
-    Ok(())
+const BEGIN_MARKER_PREFIX: &str = "<!-- aidlc-helper:begin ";
+
+/// Matches a relative `<folder>/aws-aidlc-rule-details/<rest>` reference: the folder
+/// (one or more segments) in group 1 and the path below the details tree in group 2.
+/// Backticks, link parentheses, quotes and whitespace all end a reference.
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"((?:[\w.-]+/)+)aws-aidlc-rule-details/([\w./-]*)")
+        .expect("valid reference pattern")
+});
+
+/// Patch every markdown file in `files` so each rule-details reference points at the
+/// user's chosen path, whichever agent folder upstream wrote it for. References that are
+/// part of a URL or an absolute path are left alone.
+///
+/// Returns the files whose content changed, in the order they were given.
+///
+/// Like every patch in this module, paths are relative to `root` — the project root or a
+/// staging directory mirroring it.
+pub fn patch_rule_details_path(
+    root: &Path,
+    files: &[PathBuf],
+    details_parent: &str,
+) -> Result<Vec<PathBuf>> {
+    let mut changed = Vec::new();
+    for file in files {
+        if file.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let path = root.join(file);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let patched =
+            markdown::map_prose(&content, |text| rewrite_references(text, details_parent));
+        if patched != content {
+            fs::write(&path, &patched)?;
+            changed.push(file.clone());
+        }
+    }
+    Ok(changed)
 }
 
-/// Append a commit workflow section to core-workflow.md based on user preference.
-pub fn patch_commit_workflow(rules_folder: &str, workflow: &CommitWorkflow) -> Result<()> {
-    let workflow_path = Path::new(rules_folder).join("rules/core-workflow.md");
-    if !workflow_path.exists() {
-        return Ok(());
+/// Rewrite every relative rule-details reference in `content` to live under
+/// `details_parent`. Callers pass prose only, see [`markdown::map_prose`].
+fn rewrite_references(content: &str, details_parent: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    for caps in REFERENCE.captures_iter(content) {
+        let whole = caps.get(0).expect("group 0 always matches");
+        if !is_relative_reference(content, whole.start()) {
+            continue;
+        }
+        out.push_str(&content[last..whole.start()]);
+        out.push_str(&format!(
+            "{details_parent}/aws-aidlc-rule-details/{}",
+            &caps[2]
+        ));
+        last = whole.end();
     }
+    out.push_str(&content[last..]);
+    out
+}
 
-    let section = match workflow {
-        CommitWorkflow::Conventional => CONVENTIONAL_SECTION,
-        CommitWorkflow::FreeForm => FREEFORM_SECTION,
-        CommitWorkflow::None => return Ok(()),
-    };
+/// Every relative rule-details reference in `content`, in order, e.g.
+/// `.kiro/aws-aidlc-rule-details/common/x.md`.
+pub fn rule_details_references(content: &str) -> Vec<&str> {
+    REFERENCE
+        .find_iter(content)
+        .filter(|m| is_relative_reference(content, m.start()))
+        // A reference at the end of a sentence picks up the full stop.
+        .map(|m| m.as_str().trim_end_matches('.'))
+        .collect()
+}
 
-    let mut content = fs::read_to_string(&workflow_path)?;
-    content.push_str(section);
-    fs::write(&workflow_path, content)?;
-    Ok(())
+/// False when the match at `start` continues a URL (`https://host/...`) or an absolute
+/// path (`/home/...`), which the pattern only matches from the second segment on.
+fn is_relative_reference(content: &str, start: usize) -> bool {
+    !content[..start].ends_with('/')
 }
 
-const CONVENTIONAL_SECTION: &str = r#"
+/// Set the commit workflow section of core-workflow.md to the user's preference, replacing
+/// any previous choice. [`CommitWorkflow::None`] removes the section.
+pub fn patch_commit_workflow(
+    root: &Path,
+    rules_folder: &str,
+    workflow: &CommitWorkflow,
+) -> Result<()> {
+    let section = match workflow {
+        CommitWorkflow::Conventional => Some(CONVENTIONAL_SECTION),
+        CommitWorkflow::FreeForm => Some(FREEFORM_SECTION),
+        CommitWorkflow::None => None,
+    };
+    patch_section(root, rules_folder, COMMIT_WORKFLOW_SECTION_NAME, section)
+}
 
-## MANDATORY: Commit Workflow
+const CONVENTIONAL_SECTION: &str = r#"## MANDATORY: Commit Workflow
 **CRITICAL**: Commit early and often using conventional commits. Do NOT accumulate large changes.
 
 **Rules**:
@@ -71,45 +144,256 @@ const CONVENTIONAL_SECTION: &str = r#"
 7. **Before moving to the next stage**, ensure all pending changes are committed.
 "#;
 
-const FREEFORM_SECTION: &str = r#"
-
-## Commit Reminder
+const FREEFORM_SECTION: &str = r#"## Commit Reminder
 Remember to commit your changes regularly. Small, frequent commits are easier to review and revert.
 Commit documentation artifacts (requirements, stories, plans) alongside code changes.
 "#;
 
-const RELATIVE_PATHS_SECTION: &str = r#"
-
-## MANDATORY: Relative Paths Only
+const RELATIVE_PATHS_SECTION: &str = r#"## MANDATORY: Relative Paths Only
 **CRITICAL**: All file and folder references in AI-DLC documents (aidlc-state.md, plans, requirements, stories, code summaries) MUST use paths relative to the workspace root.
 - **NEVER** use absolute paths (e.g. `/Users/...`, `/home/...`, `C:\...`).
 - The `Workspace Root` in `aidlc-state.md` MUST be `.` (dot), not an absolute path.
 - This prevents leaking personal filesystem information into version control.
 "#;
 
-/// Append the relative-paths-only rule to core-workflow.md. Always applied.
-pub fn patch_relative_paths_rule(rules_folder: &str) -> Result<()> {
-    let workflow_path = Path::new(rules_folder).join("rules/core-workflow.md");
+/// Add the relative-paths-only rule to core-workflow.md. Always applied.
+pub fn patch_relative_paths_rule(root: &Path, rules_folder: &str) -> Result<()> {
+    patch_section(
+        root,
+        rules_folder,
+        RELATIVE_PATHS_SECTION_NAME,
+        Some(RELATIVE_PATHS_SECTION),
+    )
+}
+
+/// Add a section to core-workflow.md listing the team's overlay files, so the agent knows
+/// to load them. `overlay_files` are relative to the project root.
+pub fn patch_overlay_index(
+    root: &Path,
+    rules_folder: &str,
+    overlay_files: &[PathBuf],
+) -> Result<()> {
+    patch_section(
+        root,
+        rules_folder,
+        OVERLAY_INDEX_SECTION_NAME,
+        Some(&crate::overlay::index_section(overlay_files)),
+    )
+}
+
+/// Insert, replace (`Some`) or remove (`None`) the helper section `name` in
+/// core-workflow.md. Does nothing if the file doesn't exist.
+pub fn patch_section(
+    root: &Path,
+    rules_folder: &str,
+    name: &str,
+    body: Option<&str>,
+) -> Result<()> {
+    let workflow_path = core_workflow_path(root, rules_folder);
     if !workflow_path.exists() {
         return Ok(());
     }
 
-    let mut content = fs::read_to_string(&workflow_path)?;
-    content.push_str(RELATIVE_PATHS_SECTION);
-    fs::write(&workflow_path, content)?;
+    let content = fs::read_to_string(&workflow_path).context("Failed to read core-workflow.md")?;
+    let patched = match body {
+        Some(body) => upsert_section(&content, name, body, Some(placement(name))),
+        None => remove_section(&content, name),
+    };
+    if patched != content {
+        fs::write(&workflow_path, patched)?;
+    }
+    Ok(())
+}
+
+/// Insert, replace (`Some`) or remove (`None`) the helper section `name` in the file at
+/// `path`, creating the file if needed. The file is the user's, so a new section goes at
+/// its end. A file left empty by a removal is deleted, since it only existed to hold the
+/// section.
+pub fn patch_file_section(path: &Path, name: &str, body: Option<&str>) -> Result<()> {
+    let content = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?
+    } else if body.is_none() {
+        return Ok(());
+    } else {
+        String::new()
+    };
+    let patched = match body {
+        Some(body) => upsert_section(&content, name, body, None),
+        None => remove_section(&content, name),
+    };
+    if patched.trim().is_empty() {
+        fs::remove_file(path)?;
+    } else if patched != content {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, patched)?;
+    }
     Ok(())
 }
 
+/// Where the helper section `name` goes in core-workflow.md: a heading path and the
+/// position relative to it.
+fn placement(name: &str) -> (&'static str, Position) {
+    match name {
+        RELATIVE_PATHS_SECTION_NAME => (HELPER_SECTIONS_ANCHOR, Position::Before),
+        _ => (HELPER_SECTIONS_ANCHOR, Position::After),
+    }
+}
+
+/// Replace the helper section `name` in `content` with `body`, or add it if absent.
+///
+/// A new section goes at `placement`, after any helper sections already there. When
+/// `placement` matches no section, it goes at the end of the text under the document
+/// title instead; without a `placement` or a title, at the end of `content`. An unmarked
+/// section with the same heading as `body` is replaced by the new one: in place without a
+/// `placement`, moved there with one.
+pub fn upsert_section(
+    content: &str,
+    name: &str,
+    body: &str,
+    placement: Option<(&str, Position)>,
+) -> String {
+    let block = format!(
+        "{}\n{}\n{}\n",
+        begin_marker(name),
+        body.trim(),
+        end_marker(name)
+    );
+    if let Some(range) = find_section(content, name) {
+        return markdown::splice(content, range, &block);
+    }
+    let title = markdown::title(body);
+    let Some((heading_path, position)) = placement else {
+        return title
+            .and_then(|title| markdown::replace_section(content, &title, &block).ok())
+            .unwrap_or_else(|| markdown::splice(content, content.len()..content.len(), &block));
+    };
+    let content = title
+        .and_then(|title| markdown::delete_section(content, &title).ok())
+        .unwrap_or_else(|| content.to_string());
+    let at = insertion_point(&content, heading_path, position).unwrap_or(content.len());
+    markdown::splice(&content, at..at, &block)
+}
+
+/// Offset of `position` relative to the section at `heading_path` — else the end of the
+/// text under the document title — moved past any helper sections that start there. A
+/// helper section's begin marker comes before its heading, so the end of a section can
+/// fall inside one.
+fn insertion_point(content: &str, heading_path: &str, position: Position) -> Option<usize> {
+    let mut at = markdown::offset(content, heading_path, position)
+        .ok()
+        .or_else(|| {
+            let title = markdown::title(content)?;
+            markdown::offset(content, &title, Position::Inside).ok()
+        })?;
+    let helper_sections = helper_sections(content);
+    while let Some(section) = helper_sections.iter().find(|section| {
+        (section.start < at && at < section.end)
+            || (section.start >= at && content[at..section.start].trim().is_empty())
+    }) {
+        at = section.end;
+    }
+    Some(at)
+}
+
+/// Byte ranges of every helper section in `content`, see [`find_section`].
+fn helper_sections(content: &str) -> Vec<Range<usize>> {
+    let code_blocks = markdown::code_blocks(content);
+    content
+        .match_indices(BEGIN_MARKER_PREFIX)
+        .filter(|(i, _)| !code_blocks.iter().any(|block| block.contains(i)))
+        .filter_map(|(i, _)| {
+            let (name, _) = content[i + BEGIN_MARKER_PREFIX.len()..].split_once(" -->")?;
+            find_section(content, name)
+        })
+        .collect()
+}
+
+/// Remove the helper section `name` from `content`, along with the blank lines that
+/// separated it from the preceding text. Returns `content` unchanged if it's absent.
+pub fn remove_section(content: &str, name: &str) -> String {
+    match find_section(content, name) {
+        Some(range) => markdown::splice(content, range, ""),
+        None => content.to_string(),
+    }
+}
+
+/// Byte range of the section `name`, from its begin marker through the newline after its
+/// end marker. Markers quoted inside code blocks don't count.
+fn find_section(content: &str, name: &str) -> Option<Range<usize>> {
+    let code_blocks = markdown::code_blocks(content);
+    let (start, _) = content
+        .match_indices(&begin_marker(name))
+        .find(|(i, _)| !code_blocks.iter().any(|block| block.contains(i)))?;
+    let end_marker = end_marker(name);
+    let end = start + content[start..].find(&end_marker)? + end_marker.len();
+    let end = if content[end..].starts_with('\n') {
+        end + 1
+    } else {
+        end
+    };
+    Some(start..end)
+}
+
+fn begin_marker(name: &str) -> String {
+    format!("{BEGIN_MARKER_PREFIX}{name} -->")
+}
+
+fn end_marker(name: &str) -> String {
+    format!("<!-- aidlc-helper:end {name} -->")
+}
+
+fn core_workflow_path(root: &Path, rules_folder: &str) -> PathBuf {
+    root.join(rules_folder).join("rules/core-workflow.md")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
 
-    fn setup_workflow(dir: &Path, content: &str) -> String {
+    fn setup_workflow(dir: &Path, content: &str) -> &'static str {
         let rules = dir.join("myfolder/rules");
         fs::create_dir_all(&rules).unwrap();
         fs::write(rules.join("core-workflow.md"), content).unwrap();
-        dir.join("myfolder").to_string_lossy().to_string()
+        "myfolder"
+    }
+
+    fn workflow_file(rules_folder: &str) -> [PathBuf; 1] {
+        [Path::new(rules_folder).join("rules/core-workflow.md")]
+    }
+
+    #[test]
+    fn patches_references_in_every_markdown_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let details = dir.path().join(".custom/aws-aidlc-rule-details");
+        fs::create_dir_all(details.join("common")).unwrap();
+        fs::write(
+            details.join("common/a.md"),
+            "See `.kiro/aws-aidlc-rule-details/common/b.md`\n",
+        )
+        .unwrap();
+        fs::write(details.join("common/b.md"), "# B\n").unwrap();
+        fs::write(
+            details.join("common/data.txt"),
+            ".kiro/aws-aidlc-rule-details/\n",
+        )
+        .unwrap();
+        let files = ["a.md", "b.md", "data.txt"]
+            .map(|f| PathBuf::from(".custom/aws-aidlc-rule-details/common").join(f));
+
+        let changed = patch_rule_details_path(dir.path(), &files, ".custom").unwrap();
+
+        assert_eq!(changed, [files[0].clone()]);
+        assert_eq!(
+            fs::read_to_string(details.join("common/a.md")).unwrap(),
+            "See `.custom/aws-aidlc-rule-details/common/b.md`\n"
+        );
+        assert_eq!(
+            fs::read_to_string(details.join("common/data.txt")).unwrap(),
+            ".kiro/aws-aidlc-rule-details/\n"
+        );
     }
 
     #[test]
@@ -119,7 +403,7 @@ mod tests {
             dir.path(),
             "Load from `.kiro/aws-aidlc-rule-details/common/foo.md`",
         );
-        patch_rule_details_path(&rules_folder, ".custom").unwrap();
+        patch_rule_details_path(dir.path(), &workflow_file(rules_folder), ".custom").unwrap();
         let result =
             fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
         assert!(result.contains(".custom/aws-aidlc-rule-details/common/foo.md"));
@@ -133,17 +417,83 @@ mod tests {
             dir.path(),
             "Use `.amazonq/aws-aidlc-rule-details/` directory",
         );
-        patch_rule_details_path(&rules_folder, ".myagent").unwrap();
+        patch_rule_details_path(dir.path(), &workflow_file(rules_folder), ".myagent").unwrap();
         let result =
             fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
         assert!(result.contains(".myagent/aws-aidlc-rule-details/"));
     }
 
+    #[test]
+    fn patches_references_for_any_agent_folder() {
+        let content = "\
+Load `.newagent/aws-aidlc-rule-details/common/a.md`.
+See [details](.agents/x/aws-aidlc-rule-details/inception/) and
+bare .aiassistant/aws-aidlc-rule-details/common/b.md here.
+";
+        let result = rewrite_references(content, ".custom");
+        assert_eq!(
+            result,
+            "\
+Load `.custom/aws-aidlc-rule-details/common/a.md`.
+See [details](.custom/aws-aidlc-rule-details/inception/) and
+bare .custom/aws-aidlc-rule-details/common/b.md here.
+"
+        );
+        assert_eq!(rewrite_references(&result, ".custom"), result);
+    }
+
+    #[test]
+    fn leaves_code_blocks_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "\
+Load `.kiro/aws-aidlc-rule-details/a.md`.
+
+```
+<!-- aidlc-helper:begin relative-paths -->
+cat .kiro/aws-aidlc-rule-details/a.md
+<!-- aidlc-helper:end relative-paths -->
+```
+";
+        let rules_folder = setup_workflow(dir.path(), content);
+        patch_rule_details_path(dir.path(), &workflow_file(rules_folder), ".custom").unwrap();
+        patch_relative_paths_rule(dir.path(), rules_folder).unwrap();
+
+        let result =
+            fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
+        assert!(result.starts_with("Load `.custom/aws-aidlc-rule-details/a.md`."));
+        assert!(result.contains("cat .kiro/aws-aidlc-rule-details/a.md\n"));
+        assert!(result.contains("```\n\n<!-- aidlc-helper:begin relative-paths -->\n## MANDATORY"));
+        assert_eq!(result.matches("Relative Paths Only").count(), 1);
+    }
+
+    #[test]
+    fn leaves_urls_and_absolute_paths_alone() {
+        let content = "\
+https://github.com/awslabs/aidlc-workflows/aws-aidlc-rule-details/x.md
+/home/me/.kiro/aws-aidlc-rule-details/x.md
+";
+        assert_eq!(rewrite_references(content, ".custom"), content);
+    }
+
+    #[test]
+    fn finds_rule_details_references() {
+        let content = "Load `.kiro/aws-aidlc-rule-details/common/a.md` then \
+             see .kiro/aws-aidlc-rule-details/common/b.md.\n\
+             https://example.com/x/aws-aidlc-rule-details/c.md\n";
+        assert_eq!(
+            rule_details_references(content),
+            [
+                ".kiro/aws-aidlc-rule-details/common/a.md",
+                ".kiro/aws-aidlc-rule-details/common/b.md"
+            ]
+        );
+    }
+
     #[test]
     fn appends_conventional_commit_section() {
         let dir = tempfile::tempdir().unwrap();
         let rules_folder = setup_workflow(dir.path(), "# Workflow\n");
-        patch_commit_workflow(&rules_folder, &CommitWorkflow::Conventional).unwrap();
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::Conventional).unwrap();
         let result =
             fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
         assert!(result.contains("MANDATORY: Commit Workflow"));
@@ -153,7 +503,7 @@ mod tests {
     fn appends_freeform_section() {
         let dir = tempfile::tempdir().unwrap();
         let rules_folder = setup_workflow(dir.path(), "# Workflow\n");
-        patch_commit_workflow(&rules_folder, &CommitWorkflow::FreeForm).unwrap();
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::FreeForm).unwrap();
         let result =
             fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
         assert!(result.contains("Commit Reminder"));
@@ -164,7 +514,162 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let original = "# Workflow\n";
         let rules_folder = setup_workflow(dir.path(), original);
-        patch_commit_workflow(&rules_folder, &CommitWorkflow::None).unwrap();
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::None).unwrap();
+        let result =
+            fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
+        assert_eq!(result, original);
+    }
+
+    #[test]
+    fn sections_are_wrapped_in_markers() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules_folder = setup_workflow(dir.path(), "# Workflow\n");
+        patch_relative_paths_rule(dir.path(), rules_folder).unwrap();
+        let result =
+            fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
+        assert!(result.starts_with("# Workflow\n\n<!-- aidlc-helper:begin relative-paths -->\n"));
+        assert!(result.ends_with("\n<!-- aidlc-helper:end relative-paths -->\n"));
+    }
+
+    #[test]
+    fn applying_patches_twice_is_a_no_op() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules_folder = setup_workflow(dir.path(), "# Workflow\n");
+        let path = dir.path().join("myfolder/rules/core-workflow.md");
+
+        patch_relative_paths_rule(dir.path(), rules_folder).unwrap();
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::Conventional).unwrap();
+        let once = fs::read_to_string(&path).unwrap();
+        patch_relative_paths_rule(dir.path(), rules_folder).unwrap();
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::Conventional).unwrap();
+        let twice = fs::read_to_string(&path).unwrap();
+
+        assert_eq!(once, twice);
+        assert_eq!(twice.matches("Relative Paths Only").count(), 1);
+    }
+
+    #[test]
+    fn switching_commit_style_replaces_section_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules_folder = setup_workflow(dir.path(), "# Workflow\n");
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::Conventional).unwrap();
+        patch_relative_paths_rule(dir.path(), rules_folder).unwrap();
+
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::FreeForm).unwrap();
+        let result =
+            fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
+        assert!(!result.contains("MANDATORY: Commit Workflow"));
+        assert!(result.find("Commit Reminder") < result.find("Relative Paths Only"));
+    }
+
+    #[test]
+    fn file_sections_keep_the_rest_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".github/copilot-instructions.md");
+
+        patch_file_section(&path, "aidlc", Some("Use AI-DLC.")).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "<!-- aidlc-helper:begin aidlc -->\nUse AI-DLC.\n<!-- aidlc-helper:end aidlc -->\n"
+        );
+
+        fs::write(
+            &path,
+            format!("# Team notes\n\n{}", fs::read_to_string(&path).unwrap()),
+        )
+        .unwrap();
+        patch_file_section(&path, "aidlc", Some("Use AI-DLC v2.")).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(
+            content
+                .starts_with("# Team notes\n\n<!-- aidlc-helper:begin aidlc -->\nUse AI-DLC v2.\n")
+        );
+
+        patch_file_section(&path, "aidlc", None).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Team notes\n");
+        fs::write(&path, "").unwrap();
+        patch_file_section(&path, "aidlc", None).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn removes_section_by_name() {
+        let original = "# Workflow\n";
+        let patched = upsert_section(original, "extra", "## Extra\nBody", None);
+        let patched = upsert_section(&patched, "other", "## Other", None);
+        let removed = remove_section(&patched, "extra");
+        assert_eq!(
+            removed,
+            "# Workflow\n\n<!-- aidlc-helper:begin other -->\n## Other\n<!-- aidlc-helper:end other -->\n"
+        );
+        assert_eq!(remove_section(&removed, "other"), original);
+        assert_eq!(remove_section(original, "missing"), original);
+    }
+
+    #[test]
+    fn sections_go_around_the_adaptive_workflow_principle() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules_folder = setup_workflow(
+            dir.path(),
+            "# Workflow\n\n## Adaptive Workflow Principle\n\nAdapt.\n\n### Depth\n\nDeep.\n\n## Inception\n\nStart.\n",
+        );
+        patch_relative_paths_rule(dir.path(), rules_folder).unwrap();
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::FreeForm).unwrap();
+        let path = dir.path().join("myfolder/rules/core-workflow.md");
+        let result = fs::read_to_string(&path).unwrap();
+        assert!(result.starts_with(
+            "# Workflow\n\n<!-- aidlc-helper:begin relative-paths -->\n## MANDATORY: Relative Paths Only\n"
+        ));
+        assert!(result.contains(
+            "<!-- aidlc-helper:end relative-paths -->\n\n## Adaptive Workflow Principle\n"
+        ));
+        assert!(result.contains("Deep.\n\n<!-- aidlc-helper:begin commit-workflow -->\n"));
+        assert!(
+            result
+                .ends_with("<!-- aidlc-helper:end commit-workflow -->\n\n## Inception\n\nStart.\n")
+        );
+
+        // Without the anchor, they go under the title in the order they were added.
+        setup_workflow(dir.path(), "# Workflow\n\nIntro.\n\n## Inception\n");
+        patch_relative_paths_rule(dir.path(), rules_folder).unwrap();
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::FreeForm).unwrap();
+        let result = fs::read_to_string(&path).unwrap();
+        assert!(
+            result.starts_with(
+                "# Workflow\n\nIntro.\n\n<!-- aidlc-helper:begin relative-paths -->\n"
+            )
+        );
+        assert!(result.contains(
+            "<!-- aidlc-helper:end relative-paths -->\n\n<!-- aidlc-helper:begin commit-workflow -->\n"
+        ));
+        assert!(result.ends_with("<!-- aidlc-helper:end commit-workflow -->\n\n## Inception\n"));
+    }
+
+    #[test]
+    fn unmarked_copies_of_a_section_are_taken_over() {
+        let content = "# Workflow\n\n## Extra\n\nOld.\n\n## Next\n";
+        assert_eq!(
+            upsert_section(content, "extra", "## Extra\nNew.", None),
+            "# Workflow\n\n<!-- aidlc-helper:begin extra -->\n## Extra\nNew.\n<!-- aidlc-helper:end extra -->\n\n## Next\n"
+        );
+        assert_eq!(
+            upsert_section(
+                content,
+                "extra",
+                "## Extra\nNew.",
+                Some(("Next", Position::After))
+            ),
+            "# Workflow\n\n## Next\n\n<!-- aidlc-helper:begin extra -->\n## Extra\nNew.\n<!-- aidlc-helper:end extra -->\n"
+        );
+    }
+
+    #[test]
+    fn none_removes_previous_commit_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = "# Workflow\n";
+        let rules_folder = setup_workflow(dir.path(), original);
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::FreeForm).unwrap();
+        patch_commit_workflow(dir.path(), rules_folder, &CommitWorkflow::None).unwrap();
         let result =
             fs::read_to_string(dir.path().join("myfolder/rules/core-workflow.md")).unwrap();
         assert_eq!(result, original);
@@ -0,0 +1,435 @@
+//! User-defined patches, read from `.aidlc-helper/patches.toml`.
+//!
+//! Teams that make the same edits to upstream rules after every install can commit them
+//! here instead. Each `[[patch]]` entry targets one installed file and is applied, in
+//! order, after the built-in patches in [`crate::patch`] and before the integrity manifest
+//! is written — so patched files verify as clean.
+//!
+//! `file` is relative to the installation: `rules/core-workflow.md` for the rules file and
+//! `aws-aidlc-rule-details/...` for the details tree, wherever those were installed.
+//!
+//! ```toml
+//! [[patch]]
+//! op = "find-replace"
+//! file = "rules/core-workflow.md"
+//! find = "Wait for user approval"
+//! replace = "Wait for explicit written user approval"
+//!
+//! [[patch]]
+//! op = "regex-replace"
+//! file = "aws-aidlc-rule-details/construction/code-generation.md"
+//! pattern = "commit message: (.*)"
+//! replace = "commit message: [PROJ-123] $1"
+//!
+//! [[patch]]
+//! op = "append-section"
+//! file = "rules/core-workflow.md"
+//! heading = "Adaptive Workflow Principle"   # optional: end of file when omitted
+//! position = "after"                         # "before", "after" (default) or "inside"
+//! content = """
+//! ## Ticket Convention
+//! Reference the Jira ticket in every plan.
+//! """
+//!
+//! [[patch]]
+//! op = "replace-section"
+//! file = "rules/core-workflow.md"
+//! heading = "Operations Phase"
+//! content = "## Operations Phase\nHandled by the platform team."
+//!
+//! [[patch]]
+//! op = "remove-section"
+//! file = "rules/core-workflow.md"
+//! heading = "Operations Phase"
+//! ```
+//!
+//! Like the built-in patches, `find-replace` and `regex-replace` leave fenced and indented
+//! code blocks alone — they quote commands and paths as examples. A `find` text, regex or
+//! heading that matches nothing is an error rather than a silent no-op, so a patch that
+//! stops applying after an upstream change is noticed.
+
+use crate::integrity;
+use crate::markdown::{self, Position};
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Patch file location, relative to the project root.
+pub const PATCHES_PATH: &str = ".aidlc-helper/patches.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PatchFile {
+    patch: Vec<Operation>,
+}
+
+/// One user-defined edit to an installed file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Operation {
+    /// Replace every occurrence of `find` with `replace`, outside code blocks.
+    FindReplace {
+        file: String,
+        find: String,
+        replace: String,
+    },
+    /// Replace every match of `pattern` with `replace`, which may use `$1`-style groups.
+    /// Code blocks are skipped, and a match can't span one.
+    RegexReplace {
+        file: String,
+        pattern: String,
+        replace: String,
+    },
+    /// Insert `content` relative to the section at `heading`, or at the end of the file.
+    AppendSection {
+        file: String,
+        heading: Option<String>,
+        #[serde(default = "default_position")]
+        position: Position,
+        content: String,
+    },
+    /// Replace the section at `heading`, subsections included, with `content`.
+    ReplaceSection {
+        file: String,
+        heading: String,
+        content: String,
+    },
+    /// Delete the section at `heading`, subsections included.
+    RemoveSection { file: String, heading: String },
+}
+
+fn default_position() -> Position {
+    Position::After
+}
+
+impl Operation {
+    /// The targeted file, relative to the installation.
+    pub fn file(&self) -> &str {
+        match self {
+            Operation::FindReplace { file, .. }
+            | Operation::RegexReplace { file, .. }
+            | Operation::AppendSection { file, .. }
+            | Operation::ReplaceSection { file, .. }
+            | Operation::RemoveSection { file, .. } => file,
+        }
+    }
+
+    /// Apply the operation to `content`. Fails if the text, pattern or heading it
+    /// targets isn't there.
+    fn apply(&self, content: &str) -> Result<String> {
+        Ok(match self {
+            Operation::FindReplace { find, replace, .. } => {
+                let found = Cell::new(false);
+                let patched = markdown::map_prose(content, |text| {
+                    found.set(found.get() || text.contains(find.as_str()));
+                    text.replace(find.as_str(), replace)
+                });
+                if !found.get() {
+                    bail!("text not found outside code blocks: {find:?}");
+                }
+                patched
+            }
+            Operation::RegexReplace {
+                pattern, replace, ..
+            } => {
+                let regex =
+                    Regex::new(pattern).with_context(|| format!("invalid regex {pattern:?}"))?;
+                let found = Cell::new(false);
+                let patched = markdown::map_prose(content, |text| {
+                    found.set(found.get() || regex.is_match(text));
+                    regex.replace_all(text, replace.as_str()).into_owned()
+                });
+                if !found.get() {
+                    bail!("pattern matched nothing outside code blocks: {pattern:?}");
+                }
+                patched
+            }
+            Operation::AppendSection {
+                heading: Some(heading),
+                position,
+                content: block,
+                ..
+            } => markdown::insert(content, heading, *position, block)?,
+            Operation::AppendSection {
+                heading: None,
+                content: block,
+                ..
+            } => markdown::splice(content, content.len()..content.len(), block),
+            Operation::ReplaceSection {
+                heading,
+                content: block,
+                ..
+            } => markdown::replace_section(content, heading, block)?,
+            Operation::RemoveSection { heading, .. } => markdown::delete_section(content, heading)?,
+        })
+    }
+}
+
+/// Load the project's patches. Returns none when the file doesn't exist.
+pub fn load(root: &Path) -> Result<Vec<Operation>> {
+    let path = root.join(PATCHES_PATH);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    let file: PatchFile = toml::from_str(&content)
+        .with_context(|| format!("Invalid patch file {}", path.display()))?;
+    Ok(file.patch)
+}
+
+/// Hash identifying `operations`, recorded in the integrity manifest so `diff` can tell
+/// whether the patches changed since the install. `None` when there are none. Comments
+/// and formatting in the patch file don't affect it.
+pub fn fingerprint(operations: &[Operation]) -> Option<String> {
+    if operations.is_empty() {
+        return None;
+    }
+    let serialized = serde_json::to_vec(operations).expect("operations serialize to JSON");
+    Some(integrity::hash_bytes(&serialized))
+}
+
+/// Apply `operations` in order to the installation under `root`. A `rules/` file is
+/// patched in every one of `rules_folders`.
+pub fn apply(
+    root: &Path,
+    operations: &[Operation],
+    rules_folders: &[&str],
+    details_parent: &str,
+) -> Result<()> {
+    for (index, operation) in operations.iter().enumerate() {
+        let describe = || {
+            format!(
+                "Patch #{} in {PATCHES_PATH} ({})",
+                index + 1,
+                operation.file()
+            )
+        };
+        let paths =
+            resolve(operation.file(), rules_folders, details_parent).with_context(describe)?;
+        for path in paths {
+            let path = root.join(path);
+            let content = fs::read_to_string(&path)
+                .context("file is not part of the installation")
+                .with_context(describe)?;
+            let patched = operation.apply(&content).with_context(describe)?;
+            fs::write(&path, patched)?;
+        }
+    }
+    Ok(())
+}
+
+/// Map an installation-relative path to its locations relative to the project root.
+fn resolve(file: &str, rules_folders: &[&str], details_parent: &str) -> Result<Vec<PathBuf>> {
+    let path = Path::new(file);
+    if path
+        .components()
+        .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        bail!("file must be a relative path without `..`");
+    }
+    if path.starts_with("rules") {
+        Ok(rules_folders
+            .iter()
+            .map(|rules_folder| Path::new(rules_folder).join(path))
+            .collect())
+    } else if path.starts_with("aws-aidlc-rule-details") {
+        Ok(vec![Path::new(details_parent).join(path)])
+    } else {
+        bail!("file must start with `rules/` or `aws-aidlc-rule-details/`")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str = "\
+# Workflow
+
+Wait for user approval.
+
+## Inception
+
+Plan.
+
+## Operations Phase
+
+Deploy.
+";
+
+    fn setup(root: &Path) {
+        let rules = root.join(".kiro/steering/rules");
+        fs::create_dir_all(&rules).unwrap();
+        fs::write(rules.join("core-workflow.md"), WORKFLOW).unwrap();
+        let details = root.join(".kiro/aws-aidlc-rule-details/common");
+        fs::create_dir_all(&details).unwrap();
+        fs::write(details.join("commits.md"), "commit message: fix bug\n").unwrap();
+    }
+
+    fn parse(toml: &str) -> Vec<Operation> {
+        toml::from_str::<PatchFile>(toml).unwrap().patch
+    }
+
+    #[test]
+    fn applies_operations_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        setup(dir.path());
+        let operations = parse(
+            r###"
+[[patch]]
+op = "find-replace"
+file = "rules/core-workflow.md"
+find = "user approval"
+replace = "explicit written user approval"
+
+[[patch]]
+op = "regex-replace"
+file = "aws-aidlc-rule-details/common/commits.md"
+pattern = "commit message: (.*)"
+replace = "commit message: [PROJ-123] $1"
+
+[[patch]]
+op = "append-section"
+file = "rules/core-workflow.md"
+heading = "Inception"
+content = "## Ticket Convention\nReference the Jira ticket."
+
+[[patch]]
+op = "remove-section"
+file = "rules/core-workflow.md"
+heading = "Operations Phase"
+
+[[patch]]
+op = "append-section"
+file = "rules/core-workflow.md"
+content = "## Footer"
+"###,
+        );
+
+        apply(dir.path(), &operations, &[".kiro/steering"], ".kiro").unwrap();
+
+        let workflow =
+            fs::read_to_string(dir.path().join(".kiro/steering/rules/core-workflow.md")).unwrap();
+        assert_eq!(
+            workflow,
+            "\
+# Workflow
+
+Wait for explicit written user approval.
+
+## Inception
+
+Plan.
+
+## Ticket Convention
+Reference the Jira ticket.
+
+## Footer
+"
+        );
+        let commits = fs::read_to_string(
+            dir.path()
+                .join(".kiro/aws-aidlc-rule-details/common/commits.md"),
+        )
+        .unwrap();
+        assert_eq!(commits, "commit message: [PROJ-123] fix bug\n");
+    }
+
+    #[test]
+    fn unmatched_operations_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        setup(dir.path());
+        for toml in [
+            "[[patch]]\nop = \"find-replace\"\nfile = \"rules/core-workflow.md\"\nfind = \"nope\"\nreplace = \"x\"\n",
+            "[[patch]]\nop = \"regex-replace\"\nfile = \"rules/core-workflow.md\"\npattern = \"^nope$\"\nreplace = \"x\"\n",
+            "[[patch]]\nop = \"remove-section\"\nfile = \"rules/core-workflow.md\"\nheading = \"Nope\"\n",
+            "[[patch]]\nop = \"remove-section\"\nfile = \"rules/missing.md\"\nheading = \"Inception\"\n",
+            "[[patch]]\nop = \"remove-section\"\nfile = \"../core-workflow.md\"\nheading = \"Inception\"\n",
+        ] {
+            let error = apply(dir.path(), &parse(toml), &[".kiro/steering"], ".kiro").unwrap_err();
+            assert!(format!("{error:#}").starts_with("Patch #1"), "{error:#}");
+        }
+        let workflow =
+            fs::read_to_string(dir.path().join(".kiro/steering/rules/core-workflow.md")).unwrap();
+        assert_eq!(workflow, WORKFLOW);
+    }
+
+    #[test]
+    fn replacements_skip_code_blocks() {
+        let content = "Wait for approval.\n\n```\nWait for approval.\n```\n";
+        for toml in [
+            "[[patch]]\nop = \"find-replace\"\nfile = \"rules/core-workflow.md\"\nfind = \"approval\"\nreplace = \"sign-off\"\n",
+            "[[patch]]\nop = \"regex-replace\"\nfile = \"rules/core-workflow.md\"\npattern = \"appr\\\\w+\"\nreplace = \"sign-off\"\n",
+        ] {
+            let operation = &parse(toml)[0];
+            assert_eq!(
+                operation.apply(content).unwrap(),
+                "Wait for sign-off.\n\n```\nWait for approval.\n```\n"
+            );
+            assert!(operation.apply("```\napproval\n```\n").is_err());
+        }
+    }
+
+    #[test]
+    fn rules_patches_apply_to_every_rules_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        setup(dir.path());
+        let cursor = dir.path().join(".cursor/rules/rules");
+        fs::create_dir_all(&cursor).unwrap();
+        fs::write(cursor.join("core-workflow.md"), WORKFLOW).unwrap();
+        let operations = parse(
+            "[[patch]]\nop = \"find-replace\"\nfile = \"rules/core-workflow.md\"\nfind = \"user approval\"\nreplace = \"sign-off\"\n",
+        );
+
+        apply(
+            dir.path(),
+            &operations,
+            &[".kiro/steering", ".cursor/rules"],
+            ".kiro",
+        )
+        .unwrap();
+
+        for folder in [".kiro/steering", ".cursor/rules"] {
+            let workflow =
+                fs::read_to_string(dir.path().join(folder).join("rules/core-workflow.md")).unwrap();
+            assert!(workflow.contains("Wait for sign-off."), "{folder}");
+        }
+    }
+
+    #[test]
+    fn fingerprint_ignores_formatting_but_not_content() {
+        let tight = parse(
+            "[[patch]]\nop = \"remove-section\"\nfile = \"rules/core-workflow.md\"\nheading = \"Inception\"\n",
+        );
+        let spaced = parse(
+            "# Drop a stage\n[[patch]]\nop = \"remove-section\"\nheading = \"Inception\"   # unused\nfile = \"rules/core-workflow.md\"\n",
+        );
+        let other = parse(
+            "[[patch]]\nop = \"remove-section\"\nfile = \"rules/core-workflow.md\"\nheading = \"Operations Phase\"\n",
+        );
+        assert_eq!(fingerprint(&[]), None);
+        assert!(fingerprint(&tight).is_some());
+        assert_eq!(fingerprint(&tight), fingerprint(&spaced));
+        assert_ne!(fingerprint(&tight), fingerprint(&other));
+    }
+
+    #[test]
+    fn rejects_unknown_operations_and_fields() {
+        assert!(toml::from_str::<PatchFile>("[[patch]]\nop = \"delete\"\nfile = \"x\"\n").is_err());
+        assert!(
+            toml::from_str::<PatchFile>(
+                "[[patch]]\nop = \"remove-section\"\nfile = \"x\"\nheading = \"y\"\ntypo = 1\n"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn missing_file_means_no_patches() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load(dir.path()).unwrap().is_empty());
+    }
+}
@@ -1,74 +1,104 @@
 //! Interactive CLI prompts for user input.
 //!
-//! Handles all user-facing prompts: folder selection (with presets for Kiro, Amazon Q,
-//! Cursor), overwrite confirmation, gitignore options, and commit workflow preference.
+//! Handles all user-facing prompts: agent selection (see [`crate::agent`]), overwrite
+//! confirmation, gitignore options, commit workflow preference and backup selection.
 //! Uses `dialoguer` for styled interactive selection and confirmation.
 
+use crate::agent::{self, AgentAdapter, Custom};
+use crate::config::Config;
 use anyhow::Result;
-use dialoguer::{Confirm, Input, Select};
+use dialoguer::{Confirm, Input, MultiSelect, Select};
+use serde::{Deserialize, Serialize};
 
-/// A folder preset shown in the interactive selection menu.
-struct Preset {
-    label: &'static str,
-    path: &'static str,
-    desc: &'static str,
-}
-
-const PRESETS: &[Preset] = &[
-    Preset {
-        label: ".kiro/steering",
-        path: ".kiro/steering",
-        desc: "Kiro IDE / Kiro CLI steering files",
-    },
-    Preset {
-        label: ".amazonq/rules",
-        path: ".amazonq/rules",
-        desc: "Amazon Q Developer IDE plugin",
-    },
-    Preset {
-        label: ".cursor/rules",
-        path: ".cursor/rules",
-        desc: "Cursor AI editor",
-    },
-];
-
-/// Prompt user to select a target folder for rules installation.
-/// Returns (rules_folder, rule_details_parent) — e.g. (".kiro/steering", ".kiro").
-pub fn select_folder() -> Result<(String, String)> {
-    let mut items: Vec<String> = PRESETS
+/// Prompt user to select the agents to install rules for, and any custom rules folder.
+/// At least one must be picked; the first built-in agent is selected by default.
+pub fn select_agents(config: &Config) -> Result<Vec<Box<dyn AgentAdapter>>> {
+    let agents = agent::built_in(config);
+    let mut items: Vec<String> = agents
         .iter()
-        .map(|p| format!("{} — {}", p.label, p.desc))
+        .map(|a| format!("{} — {}", a.label(), a.description()))
         .collect();
     items.push("Custom path".to_string());
+    let mut defaults = vec![false; items.len()];
+    defaults[0] = true;
 
-    let selection = Select::new()
-        .with_prompt("Where should AI-DLC rules be installed?")
-        .items(&items)
-        .default(0)
-        .interact()?;
+    let selection = loop {
+        let selection = MultiSelect::new()
+            .with_prompt("Where should AI-DLC rules be installed? (space to toggle)")
+            .items(&items)
+            .defaults(&defaults)
+            .interact()?;
+        if !selection.is_empty() {
+            break selection;
+        }
+        println!("Select at least one agent.");
+    };
 
-    let rules_folder = if selection < PRESETS.len() {
-        PRESETS[selection].path.to_string()
-    } else {
-        Input::new()
+    let custom = selection.contains(&agents.len());
+    let mut selected: Vec<Box<dyn AgentAdapter>> = agents
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| selection.contains(i))
+        .map(|(_, a)| a)
+        .collect();
+    if custom {
+        let rules_folder: String = Input::new()
             .with_prompt("Enter custom folder path (relative to project root)")
-            .interact_text()?
-    };
+            .validate_with(|input: &String| {
+                Custom::new(input).map(|_| ()).map_err(|e| e.to_string())
+            })
+            .interact_text()?;
+        selected.push(Box::new(Custom::new(&rules_folder)?));
+    }
+    Ok(selected)
+}
+
+/// Answer to the overwrite prompt.
+pub enum OverwriteChoice {
+    Overwrite,
+    ShowDiff,
+    Skip,
+}
 
-    // rule-details go one level up from the rules folder
-    let parent = rules_folder
-        .rsplit_once('/')
-        .map(|(p, _)| p.to_string())
-        .unwrap_or_else(|| rules_folder.clone());
+/// Ask whether to overwrite existing rules. With `offer_diff`, the user can also ask to
+/// see how the installed files differ from the pristine release first.
+pub fn confirm_overwrite(offer_diff: bool) -> Result<OverwriteChoice> {
+    if !offer_diff {
+        let overwrite = Confirm::new()
+            .with_prompt("Rules already exist. Overwrite? (a backup is kept)")
+            .default(false)
+            .interact()?;
+        return Ok(if overwrite {
+            OverwriteChoice::Overwrite
+        } else {
+            OverwriteChoice::Skip
+        });
+    }
 
-    Ok((rules_folder, parent))
+    let items = &[
+        "Overwrite — a backup is kept",
+        "Show diffs against the pristine release first",
+        "Keep existing rules",
+    ];
+    let selection = Select::new()
+        .with_prompt("Rules already exist. What do you want to do?")
+        .items(items)
+        .default(1)
+        .interact()?;
+    Ok(match selection {
+        0 => OverwriteChoice::Overwrite,
+        1 => OverwriteChoice::ShowDiff,
+        _ => OverwriteChoice::Skip,
+    })
 }
 
-/// Ask whether to overwrite existing rules.
-pub fn confirm_overwrite() -> Result<bool> {
-    Ok(Confirm::new()
-        .with_prompt("Rules already exist. Overwrite?")
-        .default(false)
+/// Ask which backup to restore. `items` are one-line descriptions, newest first.
+/// Returns the index of the selected item.
+pub fn select_backup(items: &[String]) -> Result<usize> {
+    Ok(Select::new()
+        .with_prompt("Which backup should be restored?")
+        .items(items)
+        .default(0)
         .interact()?)
 }
 
@@ -94,6 +124,8 @@ pub fn confirm_gitignore_aidlc_docs() -> Result<bool> {
 
 /// User's preferred commit workflow, selected during setup.
 /// Determines what (if anything) gets appended to `core-workflow.md`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum CommitWorkflow {
     Conventional,
     FreeForm,
@@ -0,0 +1,305 @@
+//! Ed25519 signatures over the integrity manifest.
+//!
+//! Anyone who can edit rule files can also regenerate the manifest, so hashes alone only
+//! catch accidents. Teams that want real tamper detection sign the manifest with a private
+//! key kept outside the repository; the public half is committed (by default to
+//! `.aidlc-helper/signing.pub`), and verification checks the signature stored next to the
+//! manifest in `.aidlc-integrity.sha256.sig`.
+//!
+//! Keys and signatures are stored as lowercase hex.
+
+use crate::config::IntegrityConfig;
+use anyhow::{Context, Result, bail};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default location of the team's public key, relative to the project root.
+pub const DEFAULT_PUBLIC_KEY_PATH: &str = ".aidlc-helper/signing.pub";
+/// Environment variable holding the path to the private signing key.
+pub const SIGNING_KEY_ENV: &str = "AIDLC_SIGNING_KEY";
+const SIGNATURE_SUFFIX: &str = ".sig";
+
+/// Outcome of checking the manifest signature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// No public key is configured, so the signature wasn't checked.
+    #[default]
+    Unchecked,
+    Valid,
+    Missing,
+    Invalid,
+}
+
+/// Whether a signature is required, and the key it must be made with.
+#[derive(Default)]
+pub struct Policy {
+    pub required: bool,
+    pub public_key: Option<VerifyingKey>,
+}
+
+impl Policy {
+    /// Build the policy from the project config. The public key is optional unless a
+    /// signature is required.
+    pub fn load(root: &Path, config: &IntegrityConfig) -> Result<Self> {
+        let key_path = root.join(
+            config
+                .public_key
+                .as_deref()
+                .unwrap_or(DEFAULT_PUBLIC_KEY_PATH),
+        );
+        let public_key = if key_path.exists() {
+            Some(read_public_key(&key_path)?)
+        } else if config.require_signature {
+            bail!(
+                "Manifest signatures are required but the public key {} does not exist",
+                key_path.display()
+            );
+        } else {
+            None
+        };
+        Ok(Self {
+            required: config.require_signature,
+            public_key,
+        })
+    }
+
+    /// Check that `key` is the private half of the configured public key, if there is
+    /// one. Signing with any other key yields a signature that [`Policy::check`] rejects.
+    pub fn check_key(&self, key: &SigningKey) -> Result<()> {
+        if let Some(public_key) = &self.public_key
+            && *public_key != key.verifying_key()
+        {
+            bail!(
+                "The signing key found via {SIGNING_KEY_ENV} (or the default location) doesn't match the project's public key"
+            );
+        }
+        Ok(())
+    }
+
+    /// Check `signature` (hex, as stored on disk) over the manifest bytes.
+    pub fn check(&self, manifest: &[u8], signature: Option<&str>) -> SignatureStatus {
+        let Some(key) = &self.public_key else {
+            return SignatureStatus::Unchecked;
+        };
+        let Some(signature) = signature else {
+            return SignatureStatus::Missing;
+        };
+        let valid = from_hex::<64>(signature.trim())
+            .map(|bytes| Signature::from_bytes(&bytes))
+            .is_some_and(|sig| key.verify(manifest, &sig).is_ok());
+        if valid {
+            SignatureStatus::Valid
+        } else {
+            SignatureStatus::Invalid
+        }
+    }
+}
+
+/// Path of the signature file for a manifest.
+pub fn signature_path(manifest_path: &Path) -> PathBuf {
+    let mut path = manifest_path.as_os_str().to_owned();
+    path.push(SIGNATURE_SUFFIX);
+    PathBuf::from(path)
+}
+
+/// Sign the manifest at `root/manifest_path` and write the signature next to it.
+pub fn sign_manifest(root: &Path, manifest_path: &Path, key: &SigningKey) -> Result<()> {
+    let manifest = fs::read(root.join(manifest_path)).context("Failed to read manifest")?;
+    let signature = key.sign(&manifest);
+    fs::write(
+        root.join(signature_path(manifest_path)),
+        format!("{}\n", to_hex(&signature.to_bytes())),
+    )?;
+    Ok(())
+}
+
+/// Generate a new signing key from the operating system's random number generator.
+pub fn generate_key() -> Result<SigningKey> {
+    let mut seed = [0u8; 32];
+    getrandom::fill(&mut seed).map_err(|e| anyhow::anyhow!("No secure randomness: {e}"))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Default location for a generated private key, outside any project.
+pub fn default_private_key_path() -> Result<PathBuf> {
+    let base = dirs::config_dir().context("Could not determine config directory")?;
+    Ok(base.join("aidlc-workflows-helper").join("signing.key"))
+}
+
+/// Find the private key: the file named by `$AIDLC_SIGNING_KEY`, or the default location
+/// if a key has been generated there. Returns `None` if neither exists.
+pub fn find_signing_key() -> Result<Option<SigningKey>> {
+    let path = match std::env::var_os(SIGNING_KEY_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => default_private_key_path()?,
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    read_private_key(&path).map(Some)
+}
+
+/// Write a private key, readable only by the current user on Unix.
+pub fn write_private_key(path: &Path, key: &SigningKey) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    std::io::Write::write_all(
+        &mut file,
+        format!("{}\n", to_hex(&key.to_bytes())).as_bytes(),
+    )?;
+    Ok(())
+}
+
+/// Write a public key.
+pub fn write_public_key(path: &Path, key: &VerifyingKey) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, format!("{}\n", to_hex(key.as_bytes())))?;
+    Ok(())
+}
+
+fn read_private_key(path: &Path) -> Result<SigningKey> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read signing key {}", path.display()))?;
+    let bytes = from_hex::<32>(content.trim())
+        .with_context(|| format!("Invalid signing key {}", path.display()))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+fn read_public_key(path: &Path) -> Result<VerifyingKey> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read public key {}", path.display()))?;
+    from_hex::<32>(content.trim())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .with_context(|| format!("Invalid public key {}", path.display()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return None;
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy_for(key: &SigningKey, required: bool) -> Policy {
+        Policy {
+            required,
+            public_key: Some(key.verifying_key()),
+        }
+    }
+
+    #[test]
+    fn signed_manifest_verifies() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = generate_key().unwrap();
+        fs::write(dir.path().join("manifest"), "{}").unwrap();
+
+        sign_manifest(dir.path(), Path::new("manifest"), &key).unwrap();
+        let signature = fs::read_to_string(dir.path().join("manifest.sig")).unwrap();
+
+        let status = policy_for(&key, true).check(b"{}", Some(&signature));
+        assert_eq!(status, SignatureStatus::Valid);
+    }
+
+    #[test]
+    fn regenerated_manifest_fails_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = generate_key().unwrap();
+        fs::write(dir.path().join("manifest"), "original").unwrap();
+        sign_manifest(dir.path(), Path::new("manifest"), &key).unwrap();
+        let signature = fs::read_to_string(dir.path().join("manifest.sig")).unwrap();
+
+        let status = policy_for(&key, true).check(b"regenerated", Some(&signature));
+        assert_eq!(status, SignatureStatus::Invalid);
+    }
+
+    #[test]
+    fn signature_from_another_key_is_invalid() {
+        let team_key = generate_key().unwrap();
+        let other_key = generate_key().unwrap();
+        let signature = to_hex(&other_key.sign(b"manifest").to_bytes());
+
+        let status = policy_for(&team_key, true).check(b"manifest", Some(&signature));
+        assert_eq!(status, SignatureStatus::Invalid);
+        let status = policy_for(&team_key, true).check(b"manifest", Some("not hex"));
+        assert_eq!(status, SignatureStatus::Invalid);
+    }
+
+    #[test]
+    fn missing_signature_and_unconfigured_key() {
+        let key = generate_key().unwrap();
+        assert_eq!(
+            policy_for(&key, true).check(b"manifest", None),
+            SignatureStatus::Missing
+        );
+        assert_eq!(
+            Policy::default().check(b"manifest", None),
+            SignatureStatus::Unchecked
+        );
+    }
+
+    #[test]
+    fn only_the_configured_key_may_sign() {
+        let team_key = generate_key().unwrap();
+        let other_key = generate_key().unwrap();
+
+        assert!(policy_for(&team_key, false).check_key(&team_key).is_ok());
+        assert!(policy_for(&team_key, false).check_key(&other_key).is_err());
+        assert!(Policy::default().check_key(&other_key).is_ok());
+    }
+
+    #[test]
+    fn keys_round_trip_through_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = generate_key().unwrap();
+        write_private_key(&dir.path().join("signing.key"), &key).unwrap();
+        write_public_key(&dir.path().join("signing.pub"), &key.verifying_key()).unwrap();
+
+        let private = read_private_key(&dir.path().join("signing.key")).unwrap();
+        let public = read_public_key(&dir.path().join("signing.pub")).unwrap();
+        assert_eq!(private.to_bytes(), key.to_bytes());
+        assert_eq!(public, key.verifying_key());
+    }
+
+    #[test]
+    fn policy_requires_public_key_when_signatures_are_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = IntegrityConfig {
+            require_signature: true,
+            ..IntegrityConfig::default()
+        };
+        assert!(Policy::load(dir.path(), &config).is_err());
+
+        let key = generate_key().unwrap();
+        write_public_key(
+            &dir.path().join(DEFAULT_PUBLIC_KEY_PATH),
+            &key.verifying_key(),
+        )
+        .unwrap();
+        let policy = Policy::load(dir.path(), &config).unwrap();
+        assert!(policy.required);
+        assert_eq!(policy.public_key, Some(key.verifying_key()));
+    }
+}
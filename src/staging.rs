@@ -0,0 +1,451 @@
+//! Transactional installs via a staging directory.
+//!
+//! The new rules tree is built under `.aidlc-staging/` in the project root, mirroring the
+//! final layout. Once every step has succeeded, [`Staging::commit`] swaps the staged paths
+//! into place with renames. Existing paths are first moved aside inside the staging
+//! directory, so if any rename fails the previous state is put back. Dropping a `Staging`
+//! removes the staged tree — a build that fails halfway never touches the project.
+//!
+//! Only one run at a time may use the staging directory: it holds an advisory lock on
+//! `.aidlc-staging/.lock` for as long as the `Staging` lives. The targets of a commit, and
+//! whether each one already existed, are written to a journal before anything is moved and
+//! removed once the swap succeeded, so a run killed in the middle of a commit is rolled
+//! back by the next one.
+
+use crate::gitignore;
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const STAGING_DIR_NAME: &str = ".aidlc-staging";
+const PREVIOUS_DIR_NAME: &str = "previous";
+const NEW_DIR_NAME: &str = "new";
+const LOCK_FILE_NAME: &str = ".lock";
+const JOURNAL_FILE_NAME: &str = "commit-targets";
+/// Journal prefix of a target that exists in the project before the commit.
+const JOURNAL_EXISTING: &str = "existing\t";
+/// Journal prefix of a target the commit creates.
+const JOURNAL_NEW: &str = "new\t";
+
+/// A staging directory for one install. The staged tree is removed when dropped.
+pub struct Staging {
+    project_root: PathBuf,
+    dir: PathBuf,
+    restored: Vec<PathBuf>,
+    _lock: fs::File,
+}
+
+impl Staging {
+    /// Lock and prepare the staging directory in `project_root`, adding it to
+    /// `.gitignore`. Fails if another run holds the lock.
+    ///
+    /// If a run was killed while committing, the targets it had already moved aside are
+    /// put back first (see [`recover`]). Other leftovers are cleared.
+    pub fn new(project_root: &Path) -> Result<Self> {
+        let dir = project_root.join(STAGING_DIR_NAME);
+        fs::create_dir_all(&dir).context("Failed to create staging directory")?;
+        let lock = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE_NAME))
+            .context("Failed to open staging lock file")?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(fs::TryLockError::WouldBlock) => bail!(
+                "Another aidlc-workflows-helper run is changing this project — wait for it to finish"
+            ),
+            Err(fs::TryLockError::Error(e)) => {
+                return Err(e).context("Failed to lock staging directory");
+            }
+        }
+        gitignore::add_to_gitignore_in(project_root, &format!("{STAGING_DIR_NAME}/"))?;
+        let mut staging = Self {
+            project_root: project_root.to_path_buf(),
+            dir,
+            restored: Vec::new(),
+            _lock: lock,
+        };
+        staging.restore_interrupted()?;
+        for leftover in [NEW_DIR_NAME, PREVIOUS_DIR_NAME] {
+            let path = staging.dir.join(leftover);
+            if path.exists() {
+                fs::remove_dir_all(&path).context("Failed to clear old staging directory")?;
+            }
+        }
+        fs::create_dir_all(staging.root()).context("Failed to create staging directory")?;
+        Ok(staging)
+    }
+
+    /// Roll back a commit that a killed run left unfinished, as recorded in the journal.
+    fn restore_interrupted(&mut self) -> Result<()> {
+        let journal = self.dir.join(JOURNAL_FILE_NAME);
+        if !journal.exists() {
+            return Ok(());
+        }
+        let content = fs::read_to_string(&journal).context("Failed to read staging journal")?;
+        for line in content.lines() {
+            if let Some(target) = line.strip_prefix(JOURNAL_NEW) {
+                // Nothing was there before, so whatever is there now came from the commit.
+                let live = self.project_root.join(target);
+                if live.exists() {
+                    remove_path(&live)?;
+                    self.restored.push(PathBuf::from(target));
+                }
+                continue;
+            }
+            let target = Path::new(line.strip_prefix(JOURNAL_EXISTING).unwrap_or(line));
+            let previous = self.dir.join(PREVIOUS_DIR_NAME).join(target);
+            if !previous.exists() {
+                continue;
+            }
+            let live = self.project_root.join(target);
+            if live.exists() {
+                remove_path(&live)?;
+            }
+            rename(&previous, &live).with_context(|| {
+                format!(
+                    "Failed to restore rules from an interrupted install — the previous version is in {}",
+                    previous.display()
+                )
+            })?;
+            self.restored.push(target.to_path_buf());
+        }
+        fs::remove_file(&journal)?;
+        Ok(())
+    }
+
+    /// Root to build the new installation under. Paths below it mirror the project root.
+    pub fn root(&self) -> PathBuf {
+        self.dir.join(NEW_DIR_NAME)
+    }
+
+    /// Copy `path` (relative to the project root) from the project into the staged tree,
+    /// for targets the build edits rather than writes from scratch. Does nothing if it
+    /// doesn't exist.
+    pub fn copy_in(&self, path: &Path) -> Result<()> {
+        let live = self.project_root.join(path);
+        if !live.is_file() {
+            return Ok(());
+        }
+        let staged = self.root().join(path);
+        if let Some(parent) = staged.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&live, &staged).with_context(|| format!("Failed to stage {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Swap each target (relative to the project root) into the project.
+    ///
+    /// The staged tree is the desired state: a target that wasn't staged is removed from
+    /// the project. On failure, targets already swapped in are removed and the previous
+    /// versions are restored before the error is returned.
+    pub fn commit(&self, targets: &[PathBuf]) -> Result<()> {
+        let journal = self.write_journal(targets)?;
+        let mut moved_aside = Vec::new();
+        let mut placed = Vec::new();
+        if let Err(e) = self.swap(targets, &mut moved_aside, &mut placed) {
+            self.rollback(&moved_aside, &placed);
+            // Kept if anything is still moved aside or a new target couldn't be removed,
+            // so the next run can finish the rollback.
+            let moved_back = !moved_aside
+                .iter()
+                .any(|t| self.dir.join(PREVIOUS_DIR_NAME).join(t).exists());
+            let new_removed = !placed
+                .iter()
+                .any(|t| !moved_aside.contains(t) && self.project_root.join(t).exists());
+            if moved_back && new_removed {
+                fs::remove_file(&journal).ok();
+            }
+            return Err(e.context("Install failed — previous rules were restored"));
+        }
+        fs::remove_file(&journal).context("Failed to remove staging journal")?;
+        Ok(())
+    }
+
+    /// Record `targets` in the journal, noting which of them exist in the project.
+    fn write_journal(&self, targets: &[PathBuf]) -> Result<PathBuf> {
+        let journal = self.dir.join(JOURNAL_FILE_NAME);
+        let lines: Vec<String> = targets
+            .iter()
+            .map(|t| {
+                let state = if self.project_root.join(t).exists() {
+                    JOURNAL_EXISTING
+                } else {
+                    JOURNAL_NEW
+                };
+                format!("{state}{}", t.to_string_lossy())
+            })
+            .collect();
+        fs::write(&journal, lines.join("\n")).context("Failed to write staging journal")?;
+        Ok(journal)
+    }
+
+    fn swap<'a>(
+        &self,
+        targets: &'a [PathBuf],
+        moved_aside: &mut Vec<&'a PathBuf>,
+        placed: &mut Vec<&'a PathBuf>,
+    ) -> Result<()> {
+        for target in targets {
+            let live = self.project_root.join(target);
+            if live.exists() {
+                rename(&live, &self.dir.join(PREVIOUS_DIR_NAME).join(target))?;
+                moved_aside.push(target);
+            }
+        }
+        for target in targets {
+            let staged = self.root().join(target);
+            if staged.exists() {
+                rename(&staged, &self.project_root.join(target))?;
+                placed.push(target);
+            }
+        }
+        Ok(())
+    }
+
+    /// Best-effort undo of a partial [`swap`](Self::swap).
+    fn rollback(&self, moved_aside: &[&PathBuf], placed: &[&PathBuf]) {
+        for target in placed {
+            remove_path(&self.project_root.join(target)).ok();
+        }
+        for target in moved_aside {
+            let previous = self.dir.join(PREVIOUS_DIR_NAME).join(target);
+            rename(&previous, &self.project_root.join(target)).ok();
+        }
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        fs::remove_dir_all(self.root()).ok();
+        // Moved-aside targets are the only copy of the user's rules until the journal is
+        // gone; leave them for the next run to restore.
+        if !self.dir.join(JOURNAL_FILE_NAME).exists() {
+            fs::remove_dir_all(self.dir.join(PREVIOUS_DIR_NAME)).ok();
+        }
+    }
+}
+
+/// Roll back a commit left unfinished by a run that was killed, if there is one. Returns
+/// the targets that were put back, or removed if the commit created them, relative to the
+/// project root. Touches nothing when
+/// there is nothing to recover.
+pub fn recover(project_root: &Path) -> Result<Vec<PathBuf>> {
+    if !project_root
+        .join(STAGING_DIR_NAME)
+        .join(JOURNAL_FILE_NAME)
+        .exists()
+    {
+        return Ok(Vec::new());
+    }
+    let mut staging = Staging::new(project_root)?;
+    Ok(std::mem::take(&mut staging.restored))
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)
+        .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn commit_replaces_existing_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("rules/old.md"), "old");
+        write(&dir.path().join("manifest"), "old manifest");
+
+        let staging = Staging::new(dir.path()).unwrap();
+        write(&staging.root().join("rules/new.md"), "new");
+        write(&staging.root().join("manifest"), "new manifest");
+        staging
+            .commit(&[PathBuf::from("rules"), PathBuf::from("manifest")])
+            .unwrap();
+        drop(staging);
+
+        assert!(!dir.path().join("rules/old.md").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("rules/new.md")).unwrap(),
+            "new"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("manifest")).unwrap(),
+            "new manifest"
+        );
+        assert!(
+            !dir.path()
+                .join(STAGING_DIR_NAME)
+                .join(NEW_DIR_NAME)
+                .exists()
+        );
+    }
+
+    #[test]
+    fn unstaged_targets_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("rules/old.md"), "old");
+        write(&dir.path().join("stale.sig"), "old signature");
+
+        let staging = Staging::new(dir.path()).unwrap();
+        write(&staging.root().join("rules/new.md"), "new");
+        staging
+            .commit(&[PathBuf::from("rules"), PathBuf::from("stale.sig")])
+            .unwrap();
+        drop(staging);
+
+        assert!(dir.path().join("rules/new.md").exists());
+        assert!(!dir.path().join("stale.sig").exists());
+    }
+
+    #[test]
+    fn failed_commit_restores_previous_state() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("rules/old.md"), "old");
+        // A file where a target's parent directory should be makes the second swap fail.
+        write(&dir.path().join("blocker"), "not a directory");
+
+        let staging = Staging::new(dir.path()).unwrap();
+        write(&staging.root().join("rules/new.md"), "new");
+        write(&staging.root().join("blocker/details/a.md"), "a");
+        let result = staging.commit(&[PathBuf::from("rules"), PathBuf::from("blocker/details")]);
+        assert!(result.is_err());
+        drop(staging);
+
+        assert_eq!(
+            fs::read_to_string(dir.path().join("rules/old.md")).unwrap(),
+            "old"
+        );
+        assert!(!dir.path().join("rules/new.md").exists());
+    }
+
+    #[test]
+    fn rollback_undoes_partial_swap() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("a/old.md"), "old a");
+
+        let staging = Staging::new(dir.path()).unwrap();
+        write(&staging.root().join("a/new.md"), "new a");
+        write(&staging.root().join("b/new.md"), "new b");
+
+        let targets = [PathBuf::from("a"), PathBuf::from("b")];
+        let mut moved_aside = Vec::new();
+        let mut placed = Vec::new();
+        staging
+            .swap(&targets, &mut moved_aside, &mut placed)
+            .unwrap();
+        staging.rollback(&moved_aside, &placed);
+
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a/old.md")).unwrap(),
+            "old a"
+        );
+        assert!(!dir.path().join("b").exists());
+    }
+
+    #[test]
+    fn dropping_without_commit_leaves_project_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("rules/old.md"), "old");
+
+        let staging = Staging::new(dir.path()).unwrap();
+        write(&staging.root().join("rules/half-written.md"), "partial");
+        drop(staging);
+
+        assert!(
+            !dir.path()
+                .join(STAGING_DIR_NAME)
+                .join(NEW_DIR_NAME)
+                .exists()
+        );
+        assert!(!dir.path().join("rules/half-written.md").exists());
+        assert!(dir.path().join("rules/old.md").exists());
+    }
+
+    #[test]
+    fn interrupted_commit_is_rolled_back_by_the_next_run() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("a/old.md"), "old a");
+        write(&dir.path().join("b/old.md"), "old b");
+
+        let staging = Staging::new(dir.path()).unwrap();
+        write(&staging.root().join("a/new.md"), "new a");
+        write(&staging.root().join("b/new.md"), "new b");
+        let targets = [PathBuf::from("a"), PathBuf::from("b")];
+        // As if killed after `a` was swapped in but before `b` was moved aside.
+        staging.write_journal(&targets).unwrap();
+        let mut moved_aside = Vec::new();
+        let mut placed = Vec::new();
+        staging
+            .swap(&targets[..1], &mut moved_aside, &mut placed)
+            .unwrap();
+        drop(staging);
+
+        assert_eq!(recover(dir.path()).unwrap(), [PathBuf::from("a")]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a/old.md")).unwrap(),
+            "old a"
+        );
+        assert!(!dir.path().join("a/new.md").exists());
+        assert!(dir.path().join("b/old.md").exists());
+        assert!(recover(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn targets_created_by_an_interrupted_commit_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        write(&dir.path().join("a/old.md"), "old a");
+
+        let staging = Staging::new(dir.path()).unwrap();
+        write(&staging.root().join("a/new.md"), "new a");
+        write(&staging.root().join("b/new.md"), "new b");
+        let targets = [PathBuf::from("a"), PathBuf::from("b")];
+        // As if killed after both were swapped in, before the journal was removed.
+        staging.write_journal(&targets).unwrap();
+        let mut moved_aside = Vec::new();
+        let mut placed = Vec::new();
+        staging
+            .swap(&targets, &mut moved_aside, &mut placed)
+            .unwrap();
+        drop(staging);
+
+        assert_eq!(
+            recover(dir.path()).unwrap(),
+            [PathBuf::from("a"), PathBuf::from("b")]
+        );
+        assert!(dir.path().join("a/old.md").exists());
+        assert!(!dir.path().join("b").exists());
+    }
+
+    #[test]
+    fn a_second_run_cannot_open_the_staging_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = Staging::new(dir.path()).unwrap();
+        fs::remove_file(dir.path().join(".gitignore")).unwrap();
+        assert!(Staging::new(dir.path()).is_err());
+        assert!(!dir.path().join(".gitignore").exists());
+        drop(staging);
+        assert!(Staging::new(dir.path()).is_ok());
+        let gitignore = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert_eq!(gitignore, ".aidlc-staging/\n");
+    }
+}